@@ -0,0 +1,41 @@
+//! Throughput of password generation: serial (on the current thread) vs parallel (one thread for every CPU).
+//! Run with `cargo bench`, the number of passwords could be given as an argument: `cargo bench -- 1000000`.
+
+extern crate dpg;
+
+use std::env;
+use std::io;
+use std::time::Instant;
+
+use dpg::option_parser::Options;
+
+fn measure(name: &str, options: &Options, repository: &[dpg::diceware_info::DicewareInfo]) {
+    let start = Instant::now();
+    dpg::passwords::write_passwords(options, repository, io::sink()).expect("passwords could not be written");
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!(
+        "{:<24}{:>10} passwords in {:>7.3}s, {:>12.0} passwords/s",
+        name,
+        options.password_count,
+        elapsed,
+        options.password_count as f64 / elapsed
+    );
+}
+
+fn main() {
+    let count = env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse::<usize>().ok())
+        .unwrap_or(200_000);
+    let repository = dpg::diceware_info::build_diceware_repository();
+    let args = vec![String::from("dpg"), String::from("-w:6"), format!("-p:{}", count), String::from("-o:plain")];
+    let serial = dpg::option_parser::parse_command_line(args).expect("invalid benchmark options");
+    let threads = dpg::parallel::available_threads();
+
+    measure("serial", &serial, &repository);
+    let parallel = Options { threads: Some(threads), ..serial.clone() };
+    measure(&format!("parallel, {} thread(s)", threads), &parallel, &repository);
+    let unordered = Options { unordered: true, ..parallel.clone() };
+    measure(&format!("unordered, {} thread(s)", threads), &unordered, &repository);
+}
@@ -0,0 +1,227 @@
+use std::str::FromStr;
+
+use dices;
+use messages::Message;
+use strength::word_entropy;
+
+/// Capitalization style applied to words of generated password.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Capitalization {
+    /// all letters lowercase (word lists contain lowercase words only, so this is the default)
+    #[default]
+    Lower,
+    /// all letters uppercase
+    Upper,
+    /// first letter of every word uppercase
+    Title,
+    /// first letter of every word except the first one uppercase
+    Camel,
+    /// all letters of one randomly chosen word uppercase
+    RandomWord,
+    /// one randomly chosen letter uppercase
+    RandomLetter,
+}
+
+/// Word (and letter within it) randomized styles capitalize, see `Capitalization::choose`.
+#[rustfmt::skip]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Choice {
+    word:   Option<usize>,
+    letter: Option<usize>,
+}
+
+impl FromStr for Capitalization {
+    type Err = Message;
+
+    fn from_str(style: &str) -> Result<Capitalization, Message> {
+        match style.to_lowercase().as_str() {
+            "lower" => Ok(Capitalization::Lower),
+            "upper" => Ok(Capitalization::Upper),
+            "title" => Ok(Capitalization::Title),
+            "camel" => Ok(Capitalization::Camel),
+            "word" => Ok(Capitalization::RandomWord),
+            "letter" => Ok(Capitalization::RandomLetter),
+            _ => Err(Message::new("error.unknown_case", &[&style])),
+        }
+    }
+}
+
+impl Capitalization {
+    /// Applies capitalization style to (already chosen) words of a password.
+    pub fn apply(self, words: &mut [String]) {
+        let choice = self.choose(words.len(), |position| words[position].as_str());
+        for (position, word) in words.iter_mut().enumerate() {
+            let mut capitalized = String::with_capacity(word.len());
+            self.push_word(&mut capitalized, word, position, choice);
+            *word = capitalized;
+        }
+    }
+
+    /// Draws random choices of randomized styles for a password of `words` words (`word` gives the word at a position).
+    /// Along with `push_word` it capitalizes words the same way as `apply`, but without allocating them.
+    pub fn choose<'a, F: Fn(usize) -> &'a str>(self, words: usize, word: F) -> Choice {
+        let mut choice = Choice { word: None, letter: None };
+        if words > 0 && (self == Capitalization::RandomWord || self == Capitalization::RandomLetter) {
+            let index = dices::get_random_number(words as u32) as usize;
+            choice.word = Some(index);
+            let letters = count_letters(word(index));
+            if self == Capitalization::RandomLetter && letters > 0 {
+                choice.letter = Some(dices::get_random_number(letters as u32) as usize);
+            }
+        }
+        choice
+    }
+
+    /// Appends `word` (being word number `position` of a password) to `password`, capitalized according to the style and `choice`.
+    pub fn push_word(self, password: &mut String, word: &str, position: usize, choice: Choice) {
+        let chosen = choice.word == Some(position);
+        match self {
+            Capitalization::Lower => password.extend(word.chars().flat_map(char::to_lowercase)),
+            Capitalization::Upper => password.extend(word.chars().flat_map(char::to_uppercase)),
+            Capitalization::RandomWord if chosen => password.extend(word.chars().flat_map(char::to_uppercase)),
+            Capitalization::Title => push_capitalized_letter(password, word, 0),
+            Capitalization::Camel if position > 0 => push_capitalized_letter(password, word, 0),
+            Capitalization::RandomLetter if chosen => match choice.letter {
+                Some(letter) => push_capitalized_nth_letter(password, word, letter),
+                None => password.push_str(word),
+            },
+            _ => password.push_str(word),
+        }
+    }
+
+    /// Entropy (in bits) added by capitalization to a password consisting of `password_length` words taken from `list`.
+    /// Only randomized styles add entropy. As the number of letters differs from word to word,
+    /// for `RandomLetter` the shortest word in the list is assumed (lower bound).
+    pub fn entropy(self, password_length: usize, list: &[String]) -> f64 {
+        match self {
+            Capitalization::RandomWord => word_entropy(password_length),
+            Capitalization::RandomLetter => {
+                let shortest = list.iter().map(|w| count_letters(w)).min().unwrap_or(0);
+                word_entropy(password_length) + word_entropy(shortest.max(1))
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+fn count_letters(word: &str) -> usize {
+    word.chars().filter(|c| c.is_alphabetic()).count()
+}
+
+/// Appends `word` with its `n`-th character uppercase.
+fn push_capitalized_letter(password: &mut String, word: &str, n: usize) {
+    for (index, c) in word.chars().enumerate() {
+        match index == n {
+            true => password.extend(c.to_uppercase()),
+            false => password.push(c),
+        }
+    }
+}
+
+/// Appends `word` with its `n`-th letter (characters other than letters are not counted) uppercase.
+fn push_capitalized_nth_letter(password: &mut String, word: &str, n: usize) {
+    let mut letter_index = 0;
+    for c in word.chars() {
+        if c.is_alphabetic() {
+            if letter_index == n {
+                password.extend(c.to_uppercase());
+            } else {
+                password.push(c);
+            }
+            letter_index += 1;
+        } else {
+            password.push(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod capitalization_tests {
+    extern crate rand;
+
+    use self::rand::{SeedableRng, XorShiftRng};
+    use super::*;
+
+    fn words() -> Vec<String> {
+        vec![s!("correct"), s!("horse"), s!("battery"), s!("staple")]
+    }
+
+    #[test]
+    fn title_case_capitalizes_every_word() {
+        let mut words = words();
+        Capitalization::Title.apply(&mut words);
+        assert_eq!(words, vec![s!("Correct"), s!("Horse"), s!("Battery"), s!("Staple")]);
+    }
+
+    #[test]
+    fn camel_case_leaves_first_word_lowercase() {
+        let mut words = words();
+        Capitalization::Camel.apply(&mut words);
+        assert_eq!(words, vec![s!("correct"), s!("Horse"), s!("Battery"), s!("Staple")]);
+    }
+
+    #[test]
+    fn upper_case_works_for_non_ascii_letters() {
+        let mut words = vec![s!("żółw")];
+        Capitalization::Upper.apply(&mut words);
+        assert_eq!(words, vec![s!("ŻÓŁW")]);
+    }
+
+    #[test]
+    fn random_word_capitalizes_exactly_one_word() {
+        let mut words = words();
+        Capitalization::RandomWord.apply(&mut words);
+        let uppercase_words = words.iter().filter(|w| w.to_uppercase() == **w).count();
+        assert_eq!(uppercase_words, 1);
+    }
+
+    #[test]
+    fn random_letter_capitalizes_exactly_one_letter() {
+        let mut words = vec![s!("yo-yo"), s!("horse")];
+        Capitalization::RandomLetter.apply(&mut words);
+        let uppercase_letters = words.iter()
+            .flat_map(|w| w.chars())
+            .filter(|c| c.is_uppercase())
+            .count();
+        assert_eq!(uppercase_letters, 1);
+    }
+
+    #[test]
+    fn words_are_capitalized_the_same_way_when_appended() {
+        let styles = [Capitalization::Lower, Capitalization::Upper, Capitalization::Title, Capitalization::Camel,
+                      Capitalization::RandomWord, Capitalization::RandomLetter];
+        for &style in &styles {
+            let list = vec![s!("yo-yo"), s!("żółw"), s!("horse")];
+            let (expected, _rng) = dices::with_rng(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])), || {
+                let mut words = list.clone();
+                style.apply(&mut words);
+                words.concat()
+            });
+            let (appended, _rng) = dices::with_rng(Box::new(XorShiftRng::from_seed([1, 2, 3, 4])), || {
+                let choice = style.choose(list.len(), |index| list[index].as_str());
+                let mut password = String::new();
+                for (position, word) in list.iter().enumerate() {
+                    style.push_word(&mut password, word, position, choice);
+                }
+                password
+            });
+            assert_eq!(appended, expected);
+        }
+    }
+
+    #[test]
+    fn only_randomized_styles_add_entropy() {
+        let list = words();
+        assert_eq!(Capitalization::Title.entropy(4, &list), 0.0);
+        assert_eq!(Capitalization::RandomWord.entropy(4, &list), 2.0);
+        // shortest word ("horse") has 5 letters
+        assert_eq!(Capitalization::RandomLetter.entropy(4, &list), 2.0 + 5f64.log2());
+    }
+
+    #[test]
+    fn styles_could_be_parsed_from_string() {
+        assert_eq!("Title".parse::<Capitalization>(), Ok(Capitalization::Title));
+        assert_eq!("letter".parse::<Capitalization>(), Ok(Capitalization::RandomLetter));
+        assert!("snake".parse::<Capitalization>().is_err());
+    }
+}
@@ -0,0 +1,274 @@
+//! Command-line utility: everything `dpg` does, with all the input and output passed explicitly,
+//! so the behaviour (output and exit codes) could be tested without spawning a process.
+
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, ErrorKind, Read, Write};
+
+use config::Config;
+use diceware_info::DicewareInfo;
+use error::DpgError;
+use messages::{Message, UiLanguage};
+use option_parser::{Command, Source};
+use output::PasswordWriter;
+use strength::PasswordStrength;
+
+/// Runs `dpg` with given command-line arguments (including the executable name) and returns the exit code.
+/// Generated passwords (and help) are written to `stdout`, errors and warnings to `stderr`.
+/// `stdin` is read by commands taking their input from the user (`check` and `roll`).
+/// Environment variables of the process are used (see `run_with_env`). Messages are written in the language chosen
+/// with `--ui-lang`, or taken from the locale (`LC_ALL`, `LC_MESSAGES` or `LANG`).
+pub fn run<I: Read, O: Write, E: Write>(args: Vec<String>, stdin: &mut I, stdout: &mut O, stderr: &mut E) -> i32 {
+    let env = env::vars().collect::<HashMap<String, String>>();
+
+    run_with_env(args, &env, stdin, stdout, stderr)
+}
+
+/// Works as `run`, but environment variables are given explicitly: they provide values of options (`DPG_WORDS`, etc.),
+/// and location of the config file (`DPG_CONFIG`, or `.config/dpg/config.toml` in the `HOME` directory).
+pub fn run_with_env<I: Read, O: Write, E: Write>(
+    args: Vec<String>,
+    env: &HashMap<String, String>,
+    stdin: &mut I,
+    stdout: &mut O,
+    stderr: &mut E,
+) -> i32 {
+    let language = UiLanguage::select(&args, env);
+    let result = Config::load(env)
+        .and_then(|config| ::option_parser::parse_command_with_defaults(args, config.as_ref(), env))
+        .and_then(|command| execute(command, language, stdin, stdout, stderr));
+
+    match result {
+        Ok(()) => 0,
+        Err(DpgError::Help(command)) => match write_output(stdout, &::option_parser::help_message_in(command.as_deref(), language)) {
+            Ok(()) => 0,
+            Err(error) => report(error, language, stderr),
+        },
+        Err(error) => report(error, language, stderr),
+    }
+}
+
+fn report<E: Write>(error: DpgError, language: UiLanguage, stderr: &mut E) -> i32 {
+    // nothing more could be done if even the error could not be written
+    let _ = writeln!(stderr, "{}", language.format("error", &[&error.message(language)]));
+    error.exit_code()
+}
+
+fn write_output<O: Write>(stdout: &mut O, output: &str) -> Result<(), DpgError> {
+    stdout.write_all(output.as_bytes())
+        .and_then(|()| stdout.flush())
+        .map_err(|error| DpgError::Output(error.to_string()))
+}
+
+fn read_input<I: Read>(stdin: &mut I) -> Result<String, DpgError> {
+    let mut input = String::new();
+    stdin.read_to_string(&mut input)
+        .map_err(|error| DpgError::ArgumentParsing(Message::new("error.stdin", &[&error])))?;
+
+    Ok(input)
+}
+
+fn execute<I: Read, O: Write, E: Write>(
+    command: Command,
+    ui_language: UiLanguage,
+    stdin: &mut I,
+    stdout: &mut O,
+    stderr: &mut E,
+) -> Result<(), DpgError> {
+    let repository = ::diceware_info::build_diceware_repository();
+    match command {
+        Command::Generate(mut options) => {
+            options.ui_language = ui_language;
+            generate(*options, repository, stdout, stderr)
+        }
+        Command::ShowConfig(settings) => write_output(stdout, &show_config(&settings, ui_language)),
+        Command::Lists => write_output(stdout, &lists(&repository, ui_language)),
+        Command::Lookup { language, keys } => {
+            write_output(stdout, &lookup(find_list(&repository, &language), &keys)?)
+        }
+        Command::Check { password } => {
+            let password = match password {
+                Some(password) => password,
+                None => read_input(stdin)?.lines().next().unwrap_or_default().to_string(),
+            };
+            write_output(stdout, &check(&repository, &password, ui_language)?)
+        }
+        Command::Roll { language, password_length, separator } => {
+            let info = find_list(&repository, &language);
+            let _ = writeln!(
+                stderr,
+                "{}",
+                ui_language.format("roll.enter", &[&password_length, &info.num_dices, &"1".repeat(info.num_dices as usize)])
+            );
+            let input = read_input(stdin)?;
+            write_output(stdout, &roll(info, password_length, &separator, &input, ui_language)?)
+        }
+        Command::Completions { shell } => write_output(stdout, &::completions::script(shell)),
+        Command::Man => write_output(stdout, &::man::page()),
+    }
+}
+
+fn find_list<'a>(repository: &'a [DicewareInfo], language: &str) -> &'a DicewareInfo {
+    repository.iter()
+        .find(|info| info.language == language)
+        .expect("language has been validated already")
+}
+
+fn generate<O: Write, E: Write>(
+    options: ::option_parser::Options,
+    repository: Vec<DicewareInfo>,
+    stdout: &mut O,
+    stderr: &mut E,
+) -> Result<(), DpgError> {
+    let excluded = ::passwords::excluded_ambiguous_words(&options, &repository);
+    if !excluded.is_empty() {
+        let _ = writeln!(stderr, "{}", options.ui_language.format("warning.ambiguous_words", &[&excluded.len(), &excluded[0]]));
+    }
+
+    // passwords copied to clipboard have to be kept in memory, all the others are written as soon as they are generated
+    let result = match options.clipboard {
+        true => ::passwords::generate_all_passwords(&options, &repository).and_then(|passwords| {
+            ::passwords::copy_to_clipboard(::passwords::join_passwords(&passwords));
+            let mut writer = PasswordWriter::new(stdout, options.format).with_language(options.ui_language);
+            passwords.iter()
+                .try_for_each(|password| writer.write(password))
+                .and_then(|()| writer.finish(&::passwords::calculate_strength(&options, &repository)))
+        }),
+        false => ::passwords::write_passwords(&options, &repository, stdout),
+    };
+
+    match result {
+        // output closed by the reader (i.e. `dpg -p:0 | head`) is the expected end of the stream
+        Err(ref error) if error.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(generation_error),
+    }
+}
+
+/// Passwords could not be generated using the options given (see `passwords::invalid_options`), or could not be written.
+fn generation_error(error: io::Error) -> DpgError {
+    match ::passwords::invalid_options_message(error) {
+        Ok(message) => DpgError::ArgumentParsing(message),
+        Err(error) => DpgError::Output(error.to_string()),
+    }
+}
+
+fn show_config(settings: &[(&str, String, Source)], language: UiLanguage) -> String {
+    let mut output = s!(language.text("settings"));
+    for &(name, ref value, ref source) in settings {
+        output.push_str(&format!("  {:<18}{:<16}{}\n", name, value, describe_source(source, language)));
+    }
+    output
+}
+
+fn describe_source(source: &Source, language: UiLanguage) -> String {
+    match *source {
+        Source::Default => s!(language.text("source.default")),
+        Source::Config(ref path, line) => language.format("source.config", &[path, &line]),
+        Source::Profile(ref name, ref path, line) => language.format("source.profile", &[name, path, &line]),
+        Source::Environment(variable) => language.format("source.environment", &[&variable]),
+        Source::CommandLine => s!(language.text("source.command_line")),
+    }
+}
+
+fn lists(repository: &[DicewareInfo], language: UiLanguage) -> String {
+    let mut output = s!(language.text("lists"));
+    for info in repository {
+        output.push_str(&language.format("lists.list", &[
+            &format!("{:<4}", info.language),
+            &format!("{:>6}", info.words.len()),
+            &info.num_dices,
+            &format!("{:.1}", ::strength::word_entropy(info.words.len())),
+        ]));
+    }
+    output
+}
+
+/// Every key is either dice rolls (looked up as a word), or a word (looked up as dice rolls).
+fn lookup(info: &DicewareInfo, keys: &[String]) -> Result<String, DpgError> {
+    let mut output = String::new();
+    for key in keys {
+        let line = match key.chars().all(|c| c.is_ascii_digit()) {
+            true => ::diceware_info::word_for_rolls(info, key)
+                .map(|word| format!("{} {}\n", key, word))
+                .map_err(DpgError::ArgumentParsing)?,
+            false => ::diceware_info::rolls_for_word(info, &key.to_lowercase())
+                .map(|rolls| format!("{} {}\n", rolls, key))
+                .ok_or_else(|| DpgError::ArgumentParsing(Message::new("lookup.not_on_list", &[key, &info.language])))?,
+        };
+        output.push_str(&line);
+    }
+    Ok(output)
+}
+
+/// Splits password into words (at characters other than letters, and where lower case letter is followed by upper case one),
+/// finds the list all the words come from and reports strength of the password, as if it has been generated using that list.
+fn check(repository: &[DicewareInfo], password: &str, language: UiLanguage) -> Result<String, DpgError> {
+    let words = split_words(password);
+    if words.is_empty() {
+        return Err(DpgError::ArgumentParsing(Message::new("check.no_password", &[])));
+    }
+    let info = repository.iter()
+        .find(|info| words.iter().all(|word| info.words.contains(word)))
+        .ok_or_else(|| DpgError::ArgumentParsing(Message::new("check.unknown_words", &[])))?;
+    let strength = PasswordStrength::from_words(info.words.len(), words.len());
+
+    Ok(language.format("check.report", &[
+        &info.language,
+        &words.len(),
+        &::strength::format_strength_in(&strength, language),
+    ]))
+}
+
+fn split_words(password: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut previous_lowercase = false;
+    for c in password.chars() {
+        let word_ends = !c.is_alphabetic() || (previous_lowercase && c.is_uppercase());
+        if word_ends && !word.is_empty() {
+            words.push(word.to_lowercase());
+            word.clear();
+        }
+        if c.is_alphabetic() {
+            word.push(c);
+        }
+        previous_lowercase = c.is_lowercase();
+    }
+    if !word.is_empty() {
+        words.push(word.to_lowercase());
+    }
+    words
+}
+
+fn roll(info: &DicewareInfo, password_length: usize, separator: &str, input: &str, language: UiLanguage) -> Result<String, DpgError> {
+    let rolls = input.split_whitespace().collect::<Vec<&str>>();
+    if rolls.len() != password_length {
+        return Err(DpgError::ArgumentParsing(Message::new("roll.count", &[&password_length, &rolls.len()])));
+    }
+    let words = rolls.iter()
+        .map(|rolls| ::diceware_info::word_for_rolls(info, rolls))
+        .collect::<Result<Vec<&str>, Message>>()
+        .map_err(DpgError::ArgumentParsing)?;
+    let strength = PasswordStrength::from_words(info.words.len(), password_length);
+
+    Ok(format!(
+        "{}{}\n\n{}",
+        language.text("generated"),
+        words.join(separator),
+        ::strength::format_strength_in(&strength, language)
+    ))
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    #[test]
+    fn passwords_are_split_into_words() {
+        assert_eq!(split_words("correct-horse.battery"), vec![s!("correct"), s!("horse"), s!("battery")]);
+        assert_eq!(split_words("Correct5Horse"), vec![s!("correct"), s!("horse")]);
+        assert_eq!(split_words("correctHorseBattery"), vec![s!("correct"), s!("horse"), s!("battery")]);
+        assert_eq!(split_words("CORRECT HORSE"), vec![s!("correct"), s!("horse")]);
+        assert!(split_words("-1-").is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+//! Shell completion scripts (`dpg completions <shell>`), generated from definitions of the commands and options
+//! (`option_parser::COMMAND_SPECS` and `OPTION_SPECS`). Names of word lists are not part of the scripts:
+//! they are taken from `dpg lists` whenever a word list is completed, so the scripts never get out of date.
+
+use std::str::FromStr;
+
+use messages::Message;
+use option_parser::{OptionSpec, OptionValue, Values, COMMAND_SPECS, OPTION_SPECS};
+
+/// Shell completion script is generated for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = Message;
+
+    fn from_str(shell: &str) -> Result<Shell, Message> {
+        match shell.to_lowercase().as_str() {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(Message::new("error.unsupported_shell", &[&shell])),
+        }
+    }
+}
+
+/// Shell command printing names of the available word lists, one per line.
+const WORD_LISTS: &str = "dpg lists 2>/dev/null | awk 'NR > 1 { print $1 }'";
+
+/// Completion script for the shell, to be sourced (bash), or saved in `$fpath` (zsh) or `~/.config/fish/completions` (fish).
+pub fn script(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+    }
+}
+
+/// Names of all the commands, including `help`.
+fn command_names() -> Vec<&'static str> {
+    COMMAND_SPECS.iter().map(|command| command.name).chain(Some("help")).collect()
+}
+
+/// Values listed as arguments of the command (if the command takes one of a few values).
+fn arguments_of(command: &str) -> Vec<&'static str> {
+    match command {
+        "completions" => vec!["bash", "zsh", "fish"],
+        "help" => command_names().into_iter().filter(|&name| name != "help").collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Description of the option in a single line, without hints (i.e. default value) given in brackets.
+pub fn summary(spec: &OptionSpec) -> String {
+    let mut summary = String::new();
+    let mut in_hint = false;
+    for c in spec.help.chars() {
+        match c {
+            '[' => in_hint = true,
+            ']' => in_hint = false,
+            c if !in_hint => summary.push(c),
+            _ => {}
+        }
+    }
+    summary.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn bash() -> String {
+    let mut script = String::from(
+        "# bash completion for dpg, generated by `dpg completions bash`\n\
+         _dpg() {\n\
+         \x20   local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\" command=gen\n\
+         \x20   if [[ $COMP_CWORD -gt 1 && ${COMP_WORDS[1]} != -* ]]; then\n\
+         \x20       command=\"${COMP_WORDS[1]}\"\n\
+         \x20   fi\n\
+         \x20   case \"$prev\" in\n",
+    );
+    for spec in OPTION_SPECS.iter().filter(|spec| matches!(spec.value, OptionValue::Required(_))) {
+        let values = match spec.values {
+            Values::Any => String::new(),
+            Values::OneOf(values) => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); ", values.join(" ")),
+            Values::WordLists => format!("COMPREPLY=($(compgen -W \"$({})\" -- \"$cur\")); ", WORD_LISTS),
+        };
+        script.push_str(&format!("        -{}|--{}) {}return ;;\n", spec.short, spec.long, values));
+    }
+    script.push_str(&format!(
+        "    esac\n\
+         \x20   if [[ $COMP_CWORD -eq 1 && $cur != -* ]]; then\n\
+         \x20       COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n\
+         \x20       return\n\
+         \x20   fi\n\
+         \x20   local words\n\
+         \x20   case \"$command\" in\n",
+        command_names().join(" ")
+    ));
+    for command in COMMAND_SPECS.iter() {
+        let mut words = ::option_parser::command_options(command)
+            .flat_map(|spec| vec![format!("-{}", spec.short), format!("--{}", spec.long)])
+            .collect::<Vec<String>>();
+        words.extend(arguments_of(command.name).iter().map(|argument| argument.to_string()));
+        script.push_str(&format!("        {}) words=\"{}\" ;;\n", command.name, words.join(" ")));
+    }
+    script.push_str(&format!(
+        "        help) words=\"{}\" ;;\n\
+         \x20   esac\n\
+         \x20   COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))\n\
+         }}\n\
+         complete -F _dpg dpg\n",
+        arguments_of("help").join(" ")
+    ));
+    script
+}
+
+/// Text in single quotes, as zsh or fish expects it.
+fn quoted(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn zsh() -> String {
+    let mut script = String::from(
+        "#compdef dpg\n\
+         # zsh completion for dpg, generated by `dpg completions zsh`\n\n\
+         _dpg_lists() {\n",
+    );
+    script.push_str(&format!(
+        "    local -a lists\n\
+         \x20   lists=(${{(f)\"$({})\"}})\n\
+         \x20   compadd -a lists\n\
+         }}\n\n\
+         _dpg() {{\n\
+         \x20   local -a commands\n\
+         \x20   commands=(\n",
+        WORD_LISTS
+    ));
+    for command in COMMAND_SPECS.iter() {
+        script.push_str(&format!("        {}\n", quoted(&format!("{}:{}", command.name, command.about))));
+    }
+    script.push_str(
+        "        'help:show help of a command'\n\
+         \x20   )\n\
+         \x20   if (( CURRENT == 2 )) && [[ $PREFIX != -* ]]; then\n\
+         \x20       _describe -t commands 'dpg command' commands\n\
+         \x20       return\n\
+         \x20   fi\n\
+         \x20   local command=gen\n\
+         \x20   if [[ ${words[2]} != -* ]]; then\n\
+         \x20       command=${words[2]}\n\
+         \x20       shift words\n\
+         \x20       (( CURRENT-- ))\n\
+         \x20   fi\n\
+         \x20   case $command in\n",
+    );
+    for command in COMMAND_SPECS.iter().map(|command| command.name).chain(Some("help")) {
+        let mut specs = match COMMAND_SPECS.iter().find(|spec| spec.name == command) {
+            Some(spec) => ::option_parser::command_options(spec).map(zsh_option).collect::<Vec<String>>(),
+            None => Vec::new(),
+        };
+        let arguments = arguments_of(command);
+        if !arguments.is_empty() {
+            specs.push(quoted(&format!(":argument:({})", arguments.join(" "))));
+        } else if COMMAND_SPECS.iter().any(|spec| spec.name == command && !spec.arguments.is_empty()) {
+            specs.push(s!("'*:argument: '"));
+        }
+        script.push_str(&format!("        {})\n            _arguments -s \\\n", command));
+        for spec in &specs {
+            script.push_str(&format!("                {} \\\n", spec));
+        }
+        script.push_str("            ;;\n");
+    }
+    script.push_str(
+        "    esac\n\
+         }\n\n\
+         _dpg \"$@\"\n",
+    );
+    script
+}
+
+fn zsh_option(spec: &OptionSpec) -> String {
+    let description = summary(spec).replace('[', "\\[").replace(']', "\\]").replace(':', "\\:");
+    let exclusive = format!("(-{} --{})", spec.short, spec.long);
+    let action = match spec.values {
+        Values::Any => s!(" "),
+        Values::OneOf(values) => format!("({})", values.join(" ")),
+        Values::WordLists => s!("_dpg_lists"),
+    };
+    match spec.value {
+        OptionValue::Flag => format!("{}{{-{},--{}}}{}", quoted(&exclusive), spec.short, spec.long, quoted(&format!("[{}]", description))),
+        OptionValue::Required(value) => format!(
+            "{}{{-{}+,--{}=}}{}",
+            quoted(&exclusive), spec.short, spec.long, quoted(&format!("[{}]:{}:{}", description, value, action))
+        ),
+        OptionValue::Optional(value) => format!(
+            "{}{{-{}-,--{}=-}}{}",
+            quoted(&exclusive), spec.short, spec.long, quoted(&format!("[{}]::{}:{}", description, value, action))
+        ),
+    }
+}
+
+fn fish() -> String {
+    let mut script = String::from(
+        "# fish completion for dpg, generated by `dpg completions fish`\n\n\
+         function __dpg_command\n\
+         \x20   set -l tokens (commandline -opc)\n\
+         \x20   if test (count $tokens) -gt 1; and not string match -q -- '-*' $tokens[2]\n\
+         \x20       echo $tokens[2]\n\
+         \x20   else\n\
+         \x20       echo gen\n\
+         \x20   end\n\
+         end\n\n\
+         function __dpg_command_is\n\
+         \x20   contains -- (__dpg_command) $argv\n\
+         end\n\n\
+         function __dpg_needs_command\n\
+         \x20   test (count (commandline -opc)) -eq 1\n\
+         end\n\n\
+         complete -c dpg -f\n",
+    );
+    for command in COMMAND_SPECS.iter() {
+        script.push_str(&format!(
+            "complete -c dpg -n __dpg_needs_command -a {} -d {}\n",
+            command.name,
+            quoted(command.about)
+        ));
+    }
+    script.push_str("complete -c dpg -n __dpg_needs_command -a help -d 'show help of a command'\n");
+    for spec in OPTION_SPECS.iter() {
+        let commands = COMMAND_SPECS.iter()
+            .filter(|command| command.options.contains(spec.short))
+            .map(|command| command.name)
+            .collect::<Vec<&str>>();
+        let values = match (spec.value, spec.values) {
+            (OptionValue::Flag, _) | (OptionValue::Optional(_), _) => String::new(),
+            (_, Values::Any) => s!(" -x"),
+            (_, Values::OneOf(values)) => format!(" -x -a {}", quoted(&values.join(" "))),
+            (_, Values::WordLists) => format!(" -x -a {}", quoted(&format!("({})", WORD_LISTS))),
+        };
+        script.push_str(&format!(
+            "complete -c dpg -n {} -s {} -l {}{} -d {}\n",
+            quoted(&format!("__dpg_command_is {}", commands.join(" "))),
+            spec.short,
+            spec.long,
+            values,
+            quoted(&summary(spec))
+        ));
+    }
+    for &command in &["completions", "help"] {
+        script.push_str(&format!(
+            "complete -c dpg -n {} -a {}\n",
+            quoted(&format!("__dpg_command_is {}", command)),
+            quoted(&arguments_of(command).join(" "))
+        ));
+    }
+    script
+}
+
+#[cfg(test)]
+mod completions_tests {
+    use super::*;
+
+    fn scripts() -> Vec<String> {
+        vec![script(Shell::Bash), script(Shell::Zsh), script(Shell::Fish)]
+    }
+
+    #[test]
+    fn shells_are_parsed_case_insensitively() {
+        assert_eq!("Bash".parse::<Shell>(), Ok(Shell::Bash));
+        assert_eq!("fish".parse::<Shell>(), Ok(Shell::Fish));
+        assert!("tcsh".parse::<Shell>().is_err());
+    }
+
+    #[test]
+    fn every_option_and_command_is_completed() {
+        for script in scripts() {
+            for spec in OPTION_SPECS.iter() {
+                assert!(script.contains(&format!("--{}", spec.long)) || script.contains(&format!("-l {}", spec.long)));
+            }
+            for command in command_names() {
+                assert!(script.contains(command));
+            }
+        }
+    }
+
+    #[test]
+    fn word_lists_are_taken_from_dpg() {
+        for script in scripts() {
+            assert!(script.contains("dpg lists"));
+            assert!(script.contains("lower"));
+            assert!(script.contains("ndjson"));
+        }
+    }
+
+    #[test]
+    fn hints_are_not_part_of_summary() {
+        assert_eq!(summary(&OPTION_SPECS[0]), "language (en or pl)");
+        assert_eq!(
+            summary(&OPTION_SPECS[1]),
+            "password length (in words) or <min>-<max> for random length"
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! Config file with default options and named profiles, i.e.:
+//!
+//! ```toml
+//! # applied every time dpg is run
+//! language = "pl"
+//! words = 6
+//! separator = "."
+//!
+//! # applied on top of the defaults when selected with `--profile wifi`
+//! [profile.wifi]
+//! words = 4
+//! case = "title"
+//! unique-words = true
+//! ```
+//!
+//! Keys are long names of options (`separator-set` could be written as `separator_set` as well), flags take `true` or `false`.
+//! Only a subset of TOML is supported: comments, `[profile.<name>]` sections, strings, numbers and booleans.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use error::DpgError;
+use messages::Message;
+use option_parser::{OptionSpec, OptionValue, OPTION_SPECS};
+
+/// Option set in the config file.
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    /// short name of the option
+    pub option:     char,
+    /// value of the option (empty for flags), `None` if the flag (or `extra`) is turned off
+    pub value:      Option<String>,
+    /// line of the config file the option is set in
+    pub line:       usize,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// path of the file config has been read from
+    pub path:       String,
+    /// settings used every time dpg is run
+    pub defaults:   Vec<Setting>,
+    /// named profiles (in order of appearance), selected with `--profile`
+    pub profiles:   Vec<(String, Vec<Setting>)>,
+}
+
+/// Value of a key, as written in the file.
+enum Value {
+    Text(String),
+    Number(String),
+    Boolean(bool),
+}
+
+impl Config {
+    /// Reads config from the file pointed to by `DPG_CONFIG`, or from `~/.config/dpg/config.toml`.
+    /// Missing default file is not an error (`None` is returned), but the one given explicitly has to exist.
+    pub fn load(env: &HashMap<String, String>) -> Result<Option<Config>, DpgError> {
+        let (path, required) = match env.get("DPG_CONFIG") {
+            Some(path) => (path.to_string(), true),
+            None => match env.get("HOME").or_else(|| env.get("USERPROFILE")) {
+                Some(home) => (format!("{}/.config/dpg/config.toml", home), false),
+                None => return Ok(None),
+            },
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Config::parse(&text, &path).map(Some),
+            Err(ref error) if !required && error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(DpgError::Config(Message::new("error.config.read", &[&path, &error]))),
+        }
+    }
+
+    /// Parses config file contents, `path` is used in error messages only.
+    pub fn parse(text: &str, path: &str) -> Result<Config, DpgError> {
+        let mut config = Config { path: path.to_string(), defaults: Vec::new(), profiles: Vec::new() };
+        let mut section: Option<usize> = None;
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let located = |message: Message| DpgError::Config(Message::wrap("error.config.location", &[&path, &number], message));
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                section = config.start_section(line).map_err(located)?;
+                continue;
+            }
+            let setting = parse_setting(line, number).map_err(located)?;
+            let settings = match section {
+                None => &mut config.defaults,
+                Some(profile) => &mut config.profiles[profile].1,
+            };
+            if settings.iter().any(|s| s.option == setting.option) {
+                return Err(located(Message::new("error.config.repeated_key", &[&line.split('=').next().unwrap_or(line).trim()])));
+            }
+            settings.push(setting);
+        }
+        Ok(config)
+    }
+
+    /// Settings of the named profile.
+    pub fn profile(&self, name: &str) -> Result<&[Setting], DpgError> {
+        self.profiles.iter()
+            .find(|profile| profile.0 == name)
+            .map(|profile| profile.1.as_slice())
+            .ok_or_else(|| {
+                let profiles = match self.profiles.is_empty() {
+                    true => Message::new("error.config.no_profiles", &[]),
+                    false => Message::new("error.config.profiles", &[
+                        &self.profiles.iter().map(|p| p.0.as_str()).collect::<Vec<&str>>().join(", "),
+                    ]),
+                };
+                DpgError::Config(Message::wrap("error.config.profile", &[&self.path, &name], profiles))
+            })
+    }
+
+    /// Starts `[defaults]` or `[profile.<name>]` section, returns index of the profile.
+    fn start_section(&mut self, line: &str) -> Result<Option<usize>, Message> {
+        let name = line.strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+            .map(|name| name.trim())
+            .ok_or_else(|| Message::new("error.config.section", &[]))?;
+        if name == "defaults" {
+            return Ok(None);
+        }
+        let profile = match name.strip_prefix("profile.") {
+            Some(profile) if is_bare_key(profile) => profile,
+            _ => return Err(Message::new("error.config.unknown_section", &[&name])),
+        };
+        if self.profiles.iter().any(|p| p.0 == profile) {
+            return Err(Message::new("error.config.repeated_profile", &[&profile]));
+        }
+        self.profiles.push((profile.to_string(), Vec::new()));
+
+        Ok(Some(self.profiles.len() - 1))
+    }
+}
+
+fn parse_setting(line: &str, number: usize) -> Result<Setting, Message> {
+    let index = line.find('=').ok_or_else(|| Message::new("error.config.key_value", &[]))?;
+    let key = line[..index].trim();
+    let spec = find_key(key).ok_or_else(|| Message::new("error.config.unknown_key", &[&key]))?;
+    let value = match (spec.value, parse_value(line[index + 1..].trim())?) {
+        (OptionValue::Flag, Value::Boolean(enabled)) | (OptionValue::Optional(_), Value::Boolean(enabled)) => {
+            if enabled { Some(String::new()) } else { None }
+        }
+        (OptionValue::Flag, _) => return Err(Message::new("error.config.boolean", &[&key])),
+        (OptionValue::Required(_), Value::Boolean(_)) => return Err(Message::new("error.config.string_or_number", &[&key])),
+        (_, Value::Text(value)) | (_, Value::Number(value)) => Some(value),
+    };
+    if let Some(value) = value.as_ref().filter(|_| spec.value != OptionValue::Flag) {
+        ::option_parser::check_value(spec.short, value)
+            .map_err(|message| Message::wrap("error.config.invalid_value", &[&key], message))?;
+    }
+
+    Ok(Setting { option: spec.short, value, line: number })
+}
+
+/// Option that could be set in the config file (every option, except `help`, `profile`, `show-config` and `ui-lang`).
+fn find_key(key: &str) -> Option<&'static OptionSpec> {
+    let key = key.replace('_', "-");
+    OPTION_SPECS.iter()
+        .filter(|spec| !["help", "profile", "show-config", "ui-lang"].contains(&spec.long))
+        .find(|spec| spec.long == key)
+}
+
+fn parse_value(value: &str) -> Result<Value, Message> {
+    if let Some(quoted) = value.strip_prefix('"') {
+        return parse_basic_string(quoted).map(Value::Text);
+    }
+    if let Some(quoted) = value.strip_prefix('\'') {
+        return match quoted.find('\'') {
+            Some(end) if quoted[end + 1..].trim().is_empty() => Ok(Value::Text(quoted[..end].to_string())),
+            _ => Err(Message::new("error.config.unterminated", &[])),
+        };
+    }
+    match value {
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        "" => Err(Message::new("error.config.missing_value", &[])),
+        number if number.parse::<f64>().is_ok() => Ok(Value::Number(number.to_string())),
+        _ => Err(Message::new("error.config.unquoted", &[&value])),
+    }
+}
+
+/// Parses string (starting after the opening quote) with escape sequences: `\"`, `\\`, `\t`, `\n`.
+fn parse_basic_string(quoted: &str) -> Result<String, Message> {
+    let mut text = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if chars.as_str().trim().is_empty() => return Ok(text),
+            '"' => return Err(Message::new("error.config.after_string", &[])),
+            '\\' => match chars.next() {
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('t') => text.push('\t'),
+                Some('n') => text.push('\n'),
+                other => return Err(Message::new("error.config.escape", &[&other.map(|c| c.to_string()).unwrap_or_default()])),
+            },
+            c => text.push(c),
+        }
+    }
+    Err(Message::new("error.config.unterminated", &[]))
+}
+
+/// Removes comment (starting with `#` outside of a string) from the line.
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..index],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+    use messages::UiLanguage;
+
+    fn parse(text: &str) -> Result<Config, DpgError> {
+        Config::parse(text, "config.toml")
+    }
+
+    #[test]
+    fn defaults_and_profiles_are_read() {
+        let config = parse("# team defaults\n\
+                            language = \"pl\"\n\
+                            words = 6\n\
+                            separator = '.'  # dots are easy to type\n\
+                            \n\
+                            [profile.wifi]\n\
+                            words = 4\n\
+                            unique_words = true\n\
+                            \n\
+                            [profile.master]\n\
+                            entropy = 100.5\n\
+                            extra = true\n\
+                            clipboard = false\n").unwrap();
+        assert_eq!(config.defaults, vec![
+            Setting { option: 'l', value: Some(s!("pl")), line: 2 },
+            Setting { option: 'w', value: Some(s!("6")), line: 3 },
+            Setting { option: 's', value: Some(s!(".")), line: 4 },
+        ]);
+        assert_eq!(config.profile("wifi").unwrap(), &[
+            Setting { option: 'w', value: Some(s!("4")), line: 7 },
+            Setting { option: 'n', value: Some(s!("")), line: 8 },
+        ]);
+        assert_eq!(config.profile("master").unwrap(), &[
+            Setting { option: 'e', value: Some(s!("100.5")), line: 11 },
+            Setting { option: 'x', value: Some(s!("")), line: 12 },
+            Setting { option: 'c', value: None, line: 13 },
+        ]);
+    }
+
+    #[test]
+    fn strings_could_contain_escaped_quotes_and_hashes() {
+        let config = parse("separator = \"\\\"#\"\ntemplate = '{word}#{word}'").unwrap();
+        assert_eq!(config.defaults[0].value, Some(s!("\"#")));
+        assert_eq!(config.defaults[1].value, Some(s!("{word}#{word}")));
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_with_location() {
+        let error = parse("words = 5\nlangauge = \"pl\"").unwrap_err();
+        assert_eq!(error.to_string(), "config.toml:2: unknown key: 'langauge'");
+        assert_eq!(error.message(UiLanguage::Pl), "config.toml:2: nieznany klucz: 'langauge'");
+    }
+
+    #[test]
+    fn invalid_values_are_reported_with_location() {
+        let error = parse("\n\n[profile.x]\ncase = \"random\"").unwrap_err();
+        assert!(error.to_string().starts_with("config.toml:4: invalid value of 'case': "));
+
+        assert!(parse("words = 0").unwrap_err().to_string().starts_with("config.toml:1: invalid value of 'words'"));
+        assert!(parse("language = \"de\"").is_err());
+        assert!(parse("clipboard = \"yes\"").is_err());
+        assert!(parse("words = true").is_err());
+        assert!(parse("separator = .").is_err());
+        assert!(parse("separator = \".").is_err());
+    }
+
+    #[test]
+    fn invalid_structure_is_reported() {
+        assert_eq!(parse("[wifi]").unwrap_err().to_string(), "config.toml:1: unknown section: '[wifi]' (expected [profile.<name>])");
+        assert!(parse("[profile.a]\n[profile.a]").is_err());
+        assert!(parse("words 5").is_err());
+        assert!(parse("words = 5\nwords = 6").is_err());
+    }
+
+    #[test]
+    fn unknown_profile_is_reported() {
+        let config = parse("[profile.wifi]\nwords = 4").unwrap();
+        let error = config.profile("master").unwrap_err();
+        assert_eq!(error.to_string(), "config.toml: profile 'master' is not defined (available profiles: wifi)");
+    }
+
+    #[test]
+    fn missing_default_config_file_is_not_an_error() {
+        let mut env = HashMap::new();
+        env.insert(s!("HOME"), s!("/nonexistent/home"));
+        assert_eq!(Config::load(&env), Ok(None));
+
+        env.insert(s!("DPG_CONFIG"), s!("/nonexistent/config.toml"));
+        assert_eq!(Config::load(&env).unwrap_err().exit_code(), 6);
+    }
+}
@@ -1,6 +1,16 @@
 extern crate rand;
 
-use self::rand::Rng;
+use std::cell::RefCell;
+
+use self::rand::chacha::ChaChaRng;
+use self::rand::{Rng, SeedableRng};
+
+thread_local! {
+    /// Random number generator used instead of the operating system's one (on the current thread), see `with_rng`.
+    static CUSTOM_RNG: RefCell<Option<Box<dyn Rng + Send>>> = RefCell::new(None);
+    /// Operating system's generator, opened once per thread (not for every random number).
+    static OS_RNG: RefCell<Option<rand::OsRng>> = const { RefCell::new(None) };
+}
 
 fn get_rnd() -> rand::OsRng {
     match rand::OsRng::new() {
@@ -12,30 +22,64 @@ fn get_rnd() -> rand::OsRng {
     }
 }
 
-pub fn get_random_number(max: u32) -> u32{
-    let mut rng = get_rnd();
-    let value = rng.gen::<u32>();
+/// Crypto secure generator (ChaCha20) seeded with 256 bits taken from the operating system's generator.
+/// Used by threads generating passwords in parallel, so they do not have to share a generator.
+pub fn secure_rng() -> Box<dyn Rng + Send> {
+    let mut os_rng = get_rnd();
+    let seed = (0..8).map(|_i| os_rng.next_u32()).collect::<Vec<u32>>();
 
-    (value % max)
+    Box::new(ChaChaRng::from_seed(&seed))
 }
 
-pub fn roll_dice() -> u32 {
-    let mut rng = get_rnd();
+/// Runs `f` taking all the random numbers (on the current thread) from `rng` instead of the operating system's
+/// crypto secure random number generator. Generator is given back along with the result, so it could be reused.
+pub fn with_rng<T, F: FnOnce() -> T>(rng: Box<dyn Rng + Send>, f: F) -> (T, Box<dyn Rng + Send>) {
+    let previous = CUSTOM_RNG.with(|custom| custom.borrow_mut().replace(rng));
+    let mut guard = RestoreRng { previous: Some(previous) };
+    let result = f();
+    let previous = guard.previous.take().expect("random number generator has been restored already");
+    let rng = CUSTOM_RNG.with(|custom| ::std::mem::replace(&mut *custom.borrow_mut(), previous))
+        .expect("random number generator has been taken away");
+
+    (result, rng)
+}
 
-    roll_dice_internal(&mut rng)
+/// Restores previously used generator if `with_rng` is left by panicking,
+/// so the custom generator is never used by accident afterwards.
+struct RestoreRng {
+    previous: Option<Option<Box<dyn Rng + Send>>>,
 }
 
-fn roll_dice_internal(rng: &mut rand::OsRng) -> u32 {
-    let value = rng.gen::<u8>();
+impl Drop for RestoreRng {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            CUSTOM_RNG.with(|custom| *custom.borrow_mut() = previous);
+        }
+    }
+}
+
+fn next_u32() -> u32 {
+    CUSTOM_RNG.with(|custom| match custom.borrow_mut().as_mut() {
+        Some(rng) => rng.next_u32(),
+        None => OS_RNG.with(|os_rng| os_rng.borrow_mut().get_or_insert_with(get_rnd).next_u32()),
+    })
+}
+
+pub fn get_random_number(max: u32) -> u32{
+    let value = next_u32();
+
+    value % max
+}
+
+pub fn roll_dice() -> u32 {
+    let value = next_u32() as u8;
 
     (value % 6 + 1) as u32
 }
 
 pub fn roll_dices(dices: u8) -> u32 {
-    let mut rng = get_rnd();
-
     (0..dices)
-        .map(|_e| roll_dice_internal(&mut rng)-1)
+        .map(|_e| roll_dice() - 1)
         .fold(0, |sum, val| sum * 6 + val)
 }
 
@@ -51,7 +95,28 @@ mod dices_tests {
     }
 
     fn max_by_dice_num(dice_num: u8) -> u32 {
-        (6 as u32).pow(dice_num as u32) - 1
+        6_u32.pow(dice_num as u32) - 1
+    }
+
+    #[test]
+    fn custom_rng_is_used_if_given() {
+        use super::rand::{SeedableRng, XorShiftRng};
+
+        let seed = [1, 2, 3, 4];
+        let (first, _rng) = dices::with_rng(Box::new(XorShiftRng::from_seed(seed)), || dices::roll_dices(5));
+        let (second, _rng) = dices::with_rng(Box::new(XorShiftRng::from_seed(seed)), || dices::roll_dices(5));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn secure_generators_are_seeded_independently() {
+        use super::rand::Rng;
+
+        let mut first = dices::secure_rng();
+        let mut second = dices::secure_rng();
+        let first = (0..8).map(|_i| first.next_u32()).collect::<Vec<u32>>();
+        let second = (0..8).map(|_i| second.next_u32()).collect::<Vec<u32>>();
+        assert_ne!(first, second);
     }
 
     #[test]
@@ -62,7 +127,7 @@ mod dices_tests {
 
     #[test]
     fn roll_dices_2_dices_should_not_exceed_35() {
-        for _i in { 0..35*10} {
+        for _i in 0..35*10 {
             let result = dices::roll_dices(2);
             assert!(result <= max_by_dice_num(2));
         }
@@ -70,7 +135,7 @@ mod dices_tests {
 
     #[test]
     fn roll_dices_3_dices_should_not_exceed_215() {
-        for _i in { 0..215*10} {
+        for _i in 0..215*10 {
             let result = dices::roll_dices(3);
             assert!(result <= max_by_dice_num(3));
         }
@@ -78,7 +143,7 @@ mod dices_tests {
 
     #[test]
     fn roll_dices_4_dices_should_not_exceed_1295() {
-        for _i in { 0..1295*10} {
+        for _i in 0..1295*10 {
             let result = dices::roll_dices(4);
             assert!(result <= max_by_dice_num(4));
         }
@@ -86,7 +151,7 @@ mod dices_tests {
 
     #[test]
     fn roll_dices_5_dices_should_not_exceed_7775() {
-        for _i in { 0..1000 } {
+        for _i in 0..1000 {
             let result = dices::roll_dices(5);
             assert!(result <= max_by_dice_num(5));
         }
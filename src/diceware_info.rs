@@ -1,4 +1,6 @@
-#[cfg_attr(rustfmt, rustfmt_skip)]
+use messages::Message;
+
+#[rustfmt::skip]
 #[derive(Clone)]
 pub struct DicewareInfo {
     pub language:   String,
@@ -28,10 +30,54 @@ pub fn build_diceware_repository() -> Vec<DicewareInfo> {
         .collect::<Vec<DicewareInfo>>()
 }
 
+/// Finds words that are prefixes of other words in the list.
+/// If the list contains such words, passwords built without separator could be ambiguous - i.e. "no" + "table" vs "notable".
+/// Lists free of such words (i.e. EFF lists) are safe to be used without separator, as every password could be split into words in one way only.
+pub fn find_prefix_words(words: &[String]) -> Vec<String> {
+    let mut sorted = words.iter().collect::<Vec<&String>>();
+    sorted.sort();
+    sorted.dedup();
+
+    // all the words starting with given word immediately follow it in sorted list
+    sorted.windows(2)
+        .filter(|pair| pair[1].starts_with(pair[0].as_str()))
+        .map(|pair| pair[0].clone())
+        .collect()
+}
+
+/// Finds the word given dice rolls (i.e. "11111") point to: rolls are read as a number in base 6 and wrapped around the list,
+/// so every word of a list shorter than the number of possible outcomes is pointed to by more than one sequence of rolls.
+pub fn word_for_rolls<'a>(info: &'a DicewareInfo, rolls: &str) -> Result<&'a str, Message> {
+    if rolls.chars().count() != info.num_dices as usize {
+        return Err(Message::new("error.rolls_per_word", &[&rolls, &info.num_dices, &info.language]));
+    }
+    let mut value = 0usize;
+    for roll in rolls.chars() {
+        match roll.to_digit(10) {
+            Some(digit) if (1..=6).contains(&digit) => value = value * 6 + digit as usize - 1,
+            _ => return Err(Message::new("error.rolls_digits", &[&rolls])),
+        }
+    }
+
+    Ok(&info.words[value % info.words.len()])
+}
+
+/// Dice rolls pointing to the word (the first sequence, if there is more than one), `None` if the word is not on the list.
+pub fn rolls_for_word(info: &DicewareInfo, word: &str) -> Option<String> {
+    let mut index = info.words.iter().position(|w| w == word)?;
+    let mut rolls = vec!['1'; info.num_dices as usize];
+    for roll in rolls.iter_mut().rev() {
+        *roll = ::std::char::from_digit((index % 6) as u32 + 1, 10).expect("dice roll is a single digit");
+        index /= 6;
+    }
+
+    Some(rolls.into_iter().collect())
+}
+
 fn read_diceware_list(language: &str) -> DicewareInfo {
     let words = get_diceware_words_by_language(language);
 
-    process_diceware_words(&words, language)
+    process_diceware_words(words, language)
 }
 
 fn get_diceware_words_by_language(language: &str) -> &str {
@@ -62,3 +108,48 @@ fn process_diceware_words(message: &str, language: &str) -> DicewareInfo {
         words,
     }
 }
+
+#[cfg(test)]
+mod diceware_info_tests {
+    use super::*;
+
+    #[test]
+    fn words_being_prefixes_of_other_words_are_found() {
+        let words = vec![s!("table"), s!("notable"), s!("no"), s!("not"), s!("cat")];
+        assert_eq!(find_prefix_words(&words), vec![s!("no"), s!("not")]);
+    }
+
+    #[test]
+    fn english_list_is_free_of_prefix_words() {
+        let english = read_diceware_list("en");
+        assert!(find_prefix_words(&english.words).is_empty());
+    }
+
+    #[test]
+    fn dice_rolls_point_to_words() {
+        let english = read_diceware_list("en");
+        assert_eq!(word_for_rolls(&english, "1111"), Ok("ardvark"));
+        assert_eq!(word_for_rolls(&english, "1112"), Ok("abandoned"));
+        assert!(word_for_rolls(&english, "11111").is_err());
+        assert!(word_for_rolls(&english, "1117").is_err());
+    }
+
+    #[test]
+    fn shorter_lists_are_wrapped_around() {
+        let polish = read_diceware_list("pl");
+        assert_eq!(word_for_rolls(&polish, "11111"), Ok("absurd"));
+        // 3888 words, so the second half of the outcomes points to the same words again
+        assert_eq!(word_for_rolls(&polish, "41111"), Ok("absurd"));
+    }
+
+    #[test]
+    fn words_point_to_their_dice_rolls() {
+        let english = read_diceware_list("en");
+        assert_eq!(rolls_for_word(&english, "abandoned"), Some(s!("1112")));
+        assert_eq!(rolls_for_word(&english, "not-a-word"), None);
+        for rolls in &["1111", "3456", "6666"] {
+            let word = word_for_rolls(&english, rolls).unwrap();
+            assert_eq!(rolls_for_word(&english, word).as_deref(), Some(*rolls));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::fmt;
+
+use messages::{Message, UiLanguage};
+
+/// Errors reported while parsing and validating options (or running the command, i.e. writing generated passwords).
+/// Every error corresponds to the exit code `dpg` (command-line utility) exits with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DpgError {
+    /// help has been requested instead of running the command, general one or of the named command (exit code: 0)
+    Help(Option<String>),
+    /// no options have been given (exit code: 1)
+    NoArguments,
+    /// value of an option is invalid, or options could not be used together (exit code: 2)
+    ArgumentParsing(Message),
+    /// option is not recognized, the closest valid option is suggested if found (exit code: 3)
+    UnknownOption(String, Option<String>),
+    /// generated passwords could not be written (exit code: 5)
+    Output(String),
+    /// config file could not be read, or is invalid - message starts with location of the problem (exit code: 6)
+    Config(Message),
+}
+
+impl DpgError {
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            DpgError::Help(_) => 0,
+            DpgError::NoArguments => 1,
+            DpgError::ArgumentParsing(_) => 2,
+            DpgError::UnknownOption(_, _) => 3,
+            DpgError::Output(_) => 5,
+            DpgError::Config(_) => 6,
+        }
+    }
+
+    /// Description of the error in the given language.
+    pub fn message(&self, language: UiLanguage) -> String {
+        match *self {
+            DpgError::Help(_) => s!(language.text("error.help")),
+            DpgError::NoArguments => s!(language.text("error.no_arguments")),
+            DpgError::ArgumentParsing(ref message) => message.in_language(language),
+            DpgError::UnknownOption(ref option, None) => language.format("error.unknown_option", &[option]),
+            DpgError::UnknownOption(ref option, Some(ref suggestion)) => {
+                language.format("error.unknown_option_suggestion", &[option, suggestion])
+            }
+            DpgError::Output(ref message) => language.format("error.output", &[message]),
+            DpgError::Config(ref message) => message.in_language(language),
+        }
+    }
+}
+
+impl fmt::Display for DpgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message(UiLanguage::En))
+    }
+}
+
+impl Error for DpgError {}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn every_error_has_its_own_exit_code() {
+        assert_eq!(DpgError::Help(None).exit_code(), 0);
+        assert_eq!(DpgError::NoArguments.exit_code(), 1);
+        assert_eq!(DpgError::ArgumentParsing(Message::new("error.threads", &[])).exit_code(), 2);
+        assert_eq!(DpgError::UnknownOption(s!("-q"), None).exit_code(), 3);
+        assert_eq!(DpgError::Output(s!("broken pipe")).exit_code(), 5);
+        assert_eq!(DpgError::Config(Message::new("error.config.unknown_key", &[&"x"])).exit_code(), 6);
+    }
+
+    #[test]
+    fn unknown_option_is_named_in_message() {
+        assert_eq!(DpgError::UnknownOption(s!("-q"), None).to_string(), "unknown option: '-q'");
+        assert_eq!(
+            DpgError::UnknownOption(s!("--wrods"), Some(s!("--words"))).to_string(),
+            "unknown option: '--wrods' (did you mean '--words'?)"
+        );
+    }
+
+    #[test]
+    fn messages_are_translated() {
+        assert_eq!(DpgError::UnknownOption(s!("-q"), None).message(UiLanguage::Pl), "nieznana opcja: '-q'");
+        assert_eq!(DpgError::Output(s!("broken pipe")).message(UiLanguage::Pl), "nie udało się zapisać haseł: broken pipe");
+        assert_eq!(
+            DpgError::ArgumentParsing(Message::new("error.threads", &[])).message(UiLanguage::Pl),
+            "liczba wątków musi być liczbą dodatnią"
+        );
+        let located = Message::wrap("error.config.location", &[&"config.toml", &1], Message::new("error.config.unknown_key", &[&"x"]));
+        assert_eq!(DpgError::Config(located.clone()).message(UiLanguage::En), "config.toml:1: unknown key: 'x'");
+        assert_eq!(DpgError::Config(located).message(UiLanguage::Pl), "config.toml:1: nieznany klucz: 'x'");
+    }
+}
@@ -0,0 +1,223 @@
+//! Extra security step of the original Diceware method (as described by Arnold G. Reinhold):
+//! one special character or digit is inserted at a random position of a randomly chosen word.
+//! Word, position and character are chosen by rolling dice, character is taken from the 6x6 table below.
+
+use dices;
+use messages::Message;
+use strength::word_entropy;
+
+/// Reinhold's table of special characters and digits.
+/// Row is chosen by the first roll, column by the second roll.
+#[rustfmt::skip]
+static EXTRA_CHARACTERS: [[char; 6]; 6] = [
+    ['~', '!', '#', '$', '%', '^'],
+    ['&', '*', '(', ')', '-', '='],
+    ['+', '[', ']', '\\', '{', '}'],
+    [':', ';', '"', '\'', '<', '>'],
+    ['?', '/', '0', '1', '2', '3'],
+    ['4', '5', '6', '7', '8', '9'],
+];
+
+/// Specifies if (and how) extra character should be inserted into generated password.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ExtraSecurity {
+    /// do not insert extra character
+    #[default]
+    Disabled,
+    /// choose word, position and character by simulating dice rolls
+    SimulatedDice,
+    /// choose word, position and character using values of physical dice rolls (each from 1 to 6)
+    PhysicalDice(Vec<u32>),
+}
+
+/// Source of dice rolls - every roll returns value from 1 to 6, or None if there are no more rolls available.
+pub trait DiceSource {
+    fn roll(&mut self) -> Option<u32>;
+}
+
+/// Dice rolls simulated using crypto secure random number generator.
+pub struct SimulatedDice;
+
+impl DiceSource for SimulatedDice {
+    fn roll(&mut self) -> Option<u32> {
+        Some(dices::roll_dice())
+    }
+}
+
+/// Values of dice rolled by the user.
+pub struct PhysicalDice<'a> {
+    rolls: &'a [u32],
+}
+
+impl<'a> PhysicalDice<'a> {
+    pub fn new(rolls: &'a [u32]) -> PhysicalDice<'a> {
+        PhysicalDice { rolls }
+    }
+}
+
+impl<'a> DiceSource for PhysicalDice<'a> {
+    fn roll(&mut self) -> Option<u32> {
+        let (first, rest) = self.rolls.split_first()?;
+        self.rolls = rest;
+        Some(*first)
+    }
+}
+
+/// Extra character along with the place it has been inserted at.
+#[rustfmt::skip]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtraCharacter {
+    /// index of the word extra character has been inserted into
+    pub word:       usize,
+    /// index of the character (in the word) extra character has been inserted before
+    pub position:   usize,
+    pub character:  char,
+}
+
+/// Parses dice rolls given as a string of digits (i.e. "3425").
+pub fn parse_dice_rolls(rolls: &str) -> Result<Vec<u32>, Message> {
+    rolls.chars()
+        .map(|c| match c.to_digit(10) {
+            Some(value) if (1..=6).contains(&value) => Ok(value),
+            _ => Err(Message::new("error.invalid_dice_roll", &[&c])),
+        })
+        .collect()
+}
+
+/// Chooses word, position and character by rolling dice and inserts the character into the password.
+/// Returns None (leaving words untouched) if dice source runs out of rolls.
+pub fn insert_extra_character<D: DiceSource>(words: &mut [String], dice: &mut D) -> Option<ExtraCharacter> {
+    if words.is_empty() {
+        return None;
+    }
+    let word = roll_index(words.len().min(6), dice)?;
+    let position = roll_index(words[word].chars().count().clamp(1, 6), dice)?;
+    let row = dice.roll()? as usize - 1;
+    let column = dice.roll()? as usize - 1;
+    let extra = ExtraCharacter {
+        word,
+        position,
+        character: EXTRA_CHARACTERS[row][column],
+    };
+
+    let byte_index = words[word].char_indices()
+        .nth(position)
+        .map(|(index, _)| index)
+        .unwrap_or_else(|| words[word].len());
+    words[word].insert(byte_index, extra.character);
+    Some(extra)
+}
+
+/// Checks if physical dice `rolls` are enough to insert extra character into a password of `password_length` words.
+/// Rolls out of range are repeated when the word is chosen, so the rolls are simulated against the password length.
+/// Position depends on the word chosen, so only its first roll is taken into account (enough for words of 6 or more letters).
+pub fn check_dice_rolls(password_length: usize, rolls: &[u32]) -> Result<(), Message> {
+    let mut words = vec![s!("xxxxxx"); password_length];
+    match insert_extra_character(&mut words, &mut PhysicalDice::new(rolls)) {
+        Some(_) => Ok(()),
+        None => Err(Message::new("error.dice_rolls_count", &[&password_length.min(6), &password_length])),
+    }
+}
+
+/// Entropy (in bits) added by inserting extra character into a password consisting of `password_length` words taken from `list`.
+/// As word lengths differ, the shortest word in the list is assumed (lower bound).
+/// Single die is used for choosing both word and position, so at most 6 words/positions are taken into account.
+pub fn extra_character_entropy(password_length: usize, list: &[String]) -> f64 {
+    let shortest = list.iter().map(|w| w.chars().count()).min().unwrap_or(1);
+
+    word_entropy(password_length.min(6)) + word_entropy(shortest.clamp(1, 6)) + word_entropy(36)
+}
+
+/// Checks if any of the `characters` could be inserted as an extra character.
+pub fn table_contains_any(characters: &str) -> bool {
+    EXTRA_CHARACTERS.iter()
+        .flat_map(|row| row.iter())
+        .any(|c| characters.contains(*c))
+}
+
+/// Rolls a single die to choose one of `count` values, rolls are repeated if result is out of range.
+/// As in the original method only the first 6 values (words, letters) could be chosen.
+fn roll_index<D: DiceSource>(count: usize, dice: &mut D) -> Option<usize> {
+    loop {
+        let value = dice.roll()? as usize - 1;
+        if value < count {
+            return Some(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod extra_security_tests {
+    use super::*;
+
+    fn words() -> Vec<String> {
+        vec![s!("correct"), s!("horse"), s!("battery"), s!("staple")]
+    }
+
+    #[test]
+    fn physical_dice_rolls_follow_the_original_table() {
+        let mut words = words();
+        // 2nd word, before 3rd letter, row 5 & column 3 => '0'
+        let rolls = vec![2, 3, 5, 3];
+        let extra = insert_extra_character(&mut words, &mut PhysicalDice::new(&rolls));
+        assert_eq!(extra, Some(ExtraCharacter { word: 1, position: 2, character: '0' }));
+        assert_eq!(words[1], "ho0rse");
+    }
+
+    #[test]
+    fn rolls_out_of_range_are_repeated() {
+        let mut words = words();
+        // 5 & 6 are out of range for 4 words, 'horse' has no 6th letter
+        let rolls = vec![5, 6, 2, 6, 1, 1, 1];
+        let extra = insert_extra_character(&mut words, &mut PhysicalDice::new(&rolls));
+        assert_eq!(extra, Some(ExtraCharacter { word: 1, position: 0, character: '~' }));
+        assert_eq!(words[1], "~horse");
+    }
+
+    #[test]
+    fn running_out_of_rolls_leaves_password_untouched() {
+        let mut words = words();
+        let rolls = vec![1, 1];
+        let extra = insert_extra_character(&mut words, &mut PhysicalDice::new(&rolls));
+        assert_eq!(extra, None);
+        assert_eq!(words, self::words());
+    }
+
+    #[test]
+    fn dice_rolls_are_checked_against_password_length() {
+        assert_eq!(check_dice_rolls(6, &[6, 6, 1, 1]), Ok(()));
+        assert!(check_dice_rolls(4, &[6, 6, 1, 1]).is_err());
+        assert_eq!(check_dice_rolls(4, &[6, 6, 1, 1, 1, 1]), Ok(()));
+        assert!(check_dice_rolls(4, &[1, 1, 1]).is_err());
+    }
+
+    #[test]
+    fn simulated_dice_insert_exactly_one_character() {
+        let mut words = words();
+        let extra = insert_extra_character(&mut words, &mut SimulatedDice);
+        assert!(extra.is_some());
+        let length: usize = words.iter().map(|w| w.chars().count()).sum();
+        assert_eq!(length, "correcthorsebatterystaple".len() + 1);
+    }
+
+    #[test]
+    fn extra_character_adds_about_10_bits_to_5_words_password() {
+        let list = vec![s!("abcd"), s!("abcdef")];
+        let entropy = extra_character_entropy(5, &list);
+        assert!((entropy - (5f64.log2() + 2.0 + 36f64.log2())).abs() < 1e-9);
+        assert!(entropy > 9.0 && entropy < 10.0);
+    }
+
+    #[test]
+    fn only_first_6_words_and_letters_could_be_chosen() {
+        let list = vec![s!("abcdefghij")];
+        // log2(6) for word, log2(6) for position, log2(36) for character
+        assert!((extra_character_entropy(10, &list) - 4.0 * 6f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dice_rolls_are_parsed_from_digits() {
+        assert_eq!(parse_dice_rolls("3425"), Ok(vec![3, 4, 2, 5]));
+        assert!(parse_dice_rolls("3470").is_err());
+    }
+}
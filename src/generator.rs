@@ -0,0 +1,285 @@
+//! Builder-style API for generating passwords from your own code:
+//!
+//! ```rust
+//! let mut generator = dpg::generator::PasswordGenerator::builder()
+//!     .list("en")
+//!     .length(6)
+//!     .separator(".")
+//!     .capitalization(dpg::capitalization::Capitalization::Title)
+//!     .build()
+//!     .expect("invalid generator settings");
+//!
+//! let password = generator.generate();
+//! let more_passwords = generator.generate_n(3);
+//!
+//! // hot paths could reuse a buffer: nothing is allocated once it has grown big enough
+//! let mut buffer = String::with_capacity(128);
+//! generator.generate_into(&mut buffer);
+//! ```
+
+extern crate rand;
+
+use self::rand::Rng;
+
+use capitalization::Capitalization;
+use diceware_info::DicewareInfo;
+use dices;
+use extra_security::ExtraSecurity;
+use option_parser::Options;
+use password::Password;
+use passwords::PreparedOptions;
+use policy::Policy;
+use strength::PasswordStrength;
+use template::Template;
+
+/// Generates passwords using settings validated when the generator has been built.
+pub struct PasswordGenerator {
+    options: Options,
+    repository: Vec<DicewareInfo>,
+    prepared: PreparedOptions,
+    /// indices of words of the password being generated by `generate_into`, kept to avoid allocating them every time
+    indices: Vec<usize>,
+    rng: Option<Box<dyn Rng + Send>>,
+}
+
+/// Collects settings of `PasswordGenerator`. Every setting has a default value, so only the ones that differ have to be set:
+/// English list, 6 words, dash ('-') as separator, no transformations and operating system's crypto secure random number generator.
+pub struct PasswordGeneratorBuilder {
+    options: Options,
+    rng: Option<Box<dyn Rng + Send>>,
+}
+
+impl PasswordGenerator {
+    pub fn builder() -> PasswordGeneratorBuilder {
+        PasswordGeneratorBuilder {
+            options: Options::default(),
+            rng: None,
+        }
+    }
+
+    /// Generates a single password.
+    pub fn generate(&mut self) -> Password {
+        self.with_rng(|prepared, _indices| prepared.generate())
+            .expect("passwords could not fail to be generated without physical dice rolls")
+    }
+
+    /// Appends a single password to `password` (without clearing it). Words are copied straight from the word list,
+    /// so nothing is allocated once `password` has enough capacity, and the generator has generated a password
+    /// (unless passwords have to be checked or modified after they are built: with policy, maximal length,
+    /// template or extra character every password is allocated).
+    ///
+    /// Given the same random numbers, the password is the same as the one `generate` would give.
+    pub fn generate_into(&mut self, password: &mut String) {
+        self.with_rng(|prepared, indices| prepared.generate_into(indices, password))
+            .expect("passwords could not fail to be generated without physical dice rolls")
+    }
+
+    /// Runs `f` taking random numbers from the generator given to the builder (if any).
+    fn with_rng<T, F: FnOnce(&PreparedOptions, &mut Vec<usize>) -> T>(&mut self, f: F) -> T {
+        let prepared = &self.prepared;
+        let indices = &mut self.indices;
+        match self.rng.take() {
+            None => f(prepared, indices),
+            Some(rng) => {
+                let (result, rng) = dices::with_rng(rng, || f(prepared, indices));
+                self.rng = Some(rng);
+                result
+            }
+        }
+    }
+
+    /// Generates `count` passwords.
+    pub fn generate_n(&mut self, count: usize) -> Vec<Password> {
+        (0..count).map(|_i| self.generate()).collect()
+    }
+
+    /// Endless iterator of passwords.
+    pub fn iter(&mut self) -> Iter<'_> {
+        Iter { generator: self }
+    }
+
+    /// Strength of a single password (the weakest one possible, if password length is chosen randomly).
+    pub fn strength(&self) -> PasswordStrength {
+        ::passwords::calculate_strength(&self.options, &self.repository)
+    }
+}
+
+impl PasswordGeneratorBuilder {
+    /// Word list (language) words are taken from ("en" or "pl").
+    pub fn list(mut self, language: &str) -> PasswordGeneratorBuilder {
+        self.options.language = language.to_lowercase();
+        self
+    }
+
+    /// Number of words every password consists of.
+    pub fn length(mut self, words: usize) -> PasswordGeneratorBuilder {
+        self.options.password_length = words;
+        self.options.password_length_max = None;
+        self
+    }
+
+    /// Number of words of every password is chosen randomly from `min..=max`.
+    pub fn length_range(mut self, min: usize, max: usize) -> PasswordGeneratorBuilder {
+        self.options.password_length = min;
+        self.options.password_length_max = Some(max);
+        self
+    }
+
+    pub fn separator(mut self, separator: &str) -> PasswordGeneratorBuilder {
+        self.options.separator = separator.to_string();
+        self.options.separator_set = None;
+        self
+    }
+
+    /// Separators are randomly chosen from `separators` (independently between each pair of words).
+    pub fn separator_set(mut self, separators: &[char]) -> PasswordGeneratorBuilder {
+        self.options.separator_set = Some(separators.to_vec());
+        self
+    }
+
+    pub fn capitalization(mut self, capitalization: Capitalization) -> PasswordGeneratorBuilder {
+        self.options.capitalization = capitalization;
+        self
+    }
+
+    /// Inserts extra character into every password (Reinhold's method) using simulated dice.
+    pub fn extra_security(mut self, extra_security: ExtraSecurity) -> PasswordGeneratorBuilder {
+        self.options.extra_security = extra_security;
+        self
+    }
+
+    /// Words do not repeat within a password.
+    pub fn unique_words(mut self, unique_words: bool) -> PasswordGeneratorBuilder {
+        self.options.unique_words = unique_words;
+        self
+    }
+
+    pub fn policy(mut self, policy: Policy) -> PasswordGeneratorBuilder {
+        self.options.policy = Some(policy);
+        self
+    }
+
+    /// Maximal length of every password (in characters).
+    pub fn max_characters(mut self, max_characters: usize) -> PasswordGeneratorBuilder {
+        self.options.max_characters = Some(max_characters);
+        self
+    }
+
+    /// Template describing structure of every password, number of words is taken from the template.
+    pub fn template(mut self, template: Template) -> PasswordGeneratorBuilder {
+        self.options.password_length = template.word_count();
+        self.options.password_length_max = None;
+        self.options.template = Some(template);
+        self
+    }
+
+    /// Words are chosen by simulating dice rolls (as in the original method).
+    pub fn simulate_dices(mut self, simulate_dices: bool) -> PasswordGeneratorBuilder {
+        self.options.simulate_dices = simulate_dices;
+        self
+    }
+
+    /// Random number generator used instead of the operating system's one, i.e. a seeded generator for reproducible results.
+    /// Please note that passwords are only as secure as the generator used.
+    pub fn rng<R: Rng + Send + 'static>(mut self, rng: R) -> PasswordGeneratorBuilder {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Validates settings and creates the generator.
+    /// Physical dice rolls are rejected, as they could be used for a single password only.
+    pub fn build(self) -> Result<PasswordGenerator, String> {
+        if let ExtraSecurity::PhysicalDice(_) = self.options.extra_security {
+            return Err(s!("physical dice rolls could not be used by a generator, use simulated dice instead"));
+        }
+        ::option_parser::check_options(&self.options).map_err(|message| message.to_string())?;
+        let repository = ::diceware_info::build_diceware_repository();
+
+        Ok(PasswordGenerator {
+            prepared: PreparedOptions::new(&self.options, &repository),
+            indices: Vec::new(),
+            options: self.options,
+            repository,
+            rng: self.rng,
+        })
+    }
+}
+
+/// Endless iterator of passwords, see `PasswordGenerator::iter`.
+pub struct Iter<'a> {
+    generator: &'a mut PasswordGenerator,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Password;
+
+    fn next(&mut self) -> Option<Password> {
+        Some(self.generator.generate())
+    }
+}
+
+#[cfg(test)]
+mod generator_tests {
+    use super::*;
+    use super::rand::{SeedableRng, XorShiftRng};
+
+    #[test]
+    fn defaults_generate_6_words_long_passwords() {
+        let mut generator = PasswordGenerator::builder().build().unwrap();
+        let password = generator.generate();
+        assert_eq!(password.words.len(), 6);
+        assert_eq!(password.list_id, "en");
+    }
+
+    #[test]
+    fn settings_are_applied() {
+        let mut generator = PasswordGenerator::builder()
+            .list("pl")
+            .length(4)
+            .separator(".")
+            .capitalization(Capitalization::Upper)
+            .build()
+            .unwrap();
+        for password in generator.generate_n(5) {
+            assert_eq!(password.list_id, "pl");
+            assert_eq!(password.rendered, password.words.join(".").to_uppercase());
+        }
+    }
+
+    #[test]
+    fn invalid_settings_are_reported() {
+        assert!(PasswordGenerator::builder().length(0).build().is_err());
+        assert!(PasswordGenerator::builder().list("xy").build().is_err());
+        assert!(PasswordGenerator::builder().length_range(6, 4).build().is_err());
+        assert!(PasswordGenerator::builder().extra_security(ExtraSecurity::PhysicalDice(vec![1, 1, 1, 1])).build().is_err());
+    }
+
+    #[test]
+    fn seeded_rng_gives_reproducible_passwords() {
+        let seed = [1, 2, 3, 4];
+        let mut first = PasswordGenerator::builder().rng(XorShiftRng::from_seed(seed)).build().unwrap();
+        let mut second = PasswordGenerator::builder().rng(XorShiftRng::from_seed(seed)).build().unwrap();
+        let first = first.iter().take(3).collect::<Vec<Password>>();
+        let second = second.iter().take(3).collect::<Vec<Password>>();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn passwords_are_appended_as_they_would_be_generated() {
+        let builders: Vec<fn() -> PasswordGeneratorBuilder> = vec![
+            || PasswordGenerator::builder(),
+            || PasswordGenerator::builder().list("pl").length_range(3, 5).separator_set(&['.', '_']).unique_words(true),
+            || PasswordGenerator::builder().capitalization(Capitalization::RandomLetter).simulate_dices(true),
+            || PasswordGenerator::builder().length(4).max_characters(30),
+        ];
+        for builder in builders {
+            let mut generated = builder().rng(XorShiftRng::from_seed([1, 2, 3, 4])).build().unwrap();
+            let mut appended = builder().rng(XorShiftRng::from_seed([1, 2, 3, 4])).build().unwrap();
+            for _i in 0..20 {
+                let mut password = s!("> ");
+                appended.generate_into(&mut password);
+                assert_eq!(password, format!("> {}", generated.generate()));
+            }
+        }
+    }
+}
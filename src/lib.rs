@@ -1,14 +1,38 @@
 #[macro_use]
 pub mod macros;
+pub mod capitalization;
+pub mod cli;
+pub mod completions;
+pub mod config;
 pub mod dices;
 pub mod diceware_info;
+pub mod error;
+pub mod extra_security;
+pub mod generator;
+pub mod man;
+pub mod messages;
 pub mod option_parser;
+pub mod output;
+pub mod parallel;
+pub mod password;
 pub mod passwords;
+pub mod policy;
+pub mod strength;
+pub mod template;
 
 
 /// This should be not used directly. It has been created only for the sake of running tests.
-pub fn generate_diceware_passwords(options: option_parser::Options) -> String {
+pub fn generate_diceware_passwords(options: option_parser::Options) -> Result<Vec<password::Password>, messages::Message> {
     let diceware_repository = self::diceware_info::build_diceware_repository();
 
     passwords::generate_diceware_passwords(&options, diceware_repository)
 }
+
+/// Works as generate_diceware_passwords, but additionally returns strength (entropy) of generated password(s).
+pub fn generate_diceware_passwords_with_strength(
+    options: option_parser::Options,
+) -> Result<passwords::GeneratedPasswords, messages::Message> {
+    let diceware_repository = self::diceware_info::build_diceware_repository();
+
+    passwords::generate_diceware_passwords_with_strength(&options, diceware_repository)
+}
@@ -1,15 +1,11 @@
 use std::env;
+use std::io;
+use std::process::exit;
 extern crate dpg;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    let options = dpg::option_parser::parse_command_line(args);
-
-    #[cfg(debug_assertions)]
-    println!("Options: {:?}", options);
-
-    let passwords = dpg::generate_diceware_passwords(options);
-
-    println!("generated password(s):\n{}", passwords);
+    let exit_code = dpg::cli::run(args, &mut io::stdin(), &mut io::stdout(), &mut io::stderr());
+    exit(exit_code);
 }
@@ -0,0 +1,117 @@
+//! Manual page of `dpg` (`dpg man`) in roff format, generated from definitions of the commands and options
+//! (`option_parser::COMMAND_SPECS` and `OPTION_SPECS`), i.e. `dpg man > /usr/local/share/man/man1/dpg.1`.
+
+use option_parser::{OptionSpec, OptionValue, COMMAND_SPECS, OPTION_SPECS};
+
+/// Manual page (section 1) of the command-line utility.
+pub fn page() -> String {
+    let mut page = format!(
+        ".TH DPG 1 \"\" \"dpg {}\" \"User Commands\"\n\
+         .SH NAME\n\
+         dpg \\- diceware password generator\n\
+         .SH SYNOPSIS\n\
+         .B dpg\n\
+         [\\fIcommand\\fR] [\\fIoptions\\fR] [\\fIarguments\\fR]\n\
+         .SH DESCRIPTION\n\
+         Generates passwords consisting of words randomly chosen from diceware word lists \
+         (English or Polish), and estimates their strength.\n\
+         Options could be given as \\fB\\-w:5\\fR, \\fB\\-w 5\\fR, \\fB\\-w5\\fR, \\fB\\-\\-words 5\\fR \
+         or \\fB\\-\\-words=5\\fR; optional values have to be attached to the option.\n",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    page.push_str(".SH COMMANDS\n");
+    for command in COMMAND_SPECS.iter() {
+        page.push_str(&format!(".TP\n\\fB{}\\fR", command.name));
+        if !command.arguments.is_empty() {
+            page.push_str(&format!(" \\fI{}\\fR", escape(command.arguments)));
+        }
+        page.push_str(&format!("\n{}", escape(command.about)));
+        let options = ::option_parser::command_options(command)
+            .map(|spec| format!("\\fB\\-{}\\fR", spec.short))
+            .collect::<Vec<String>>();
+        page.push_str(&format!(" (options: {})\n", options.join(", ")));
+    }
+    page.push_str(".TP\n\\fBhelp\\fR [\\fIcommand\\fR]\nshow help of the command\n");
+
+    page.push_str(".SH OPTIONS\n");
+    for spec in OPTION_SPECS.iter() {
+        page.push_str(&format!(".TP\n{}\n", usage(spec)));
+        for line in spec.help.lines() {
+            page.push_str(&escape(line.split_whitespace().collect::<Vec<&str>>().join(" ").trim()));
+            page.push('\n');
+        }
+    }
+
+    page.push_str(".SH ENVIRONMENT\n");
+    for spec in OPTION_SPECS.iter().filter(|spec| !spec.env.is_empty()) {
+        page.push_str(&format!(".TP\n.B {}\nvalue of \\fB\\-\\-{}\\fR\n", escape(spec.env), escape(spec.long)));
+    }
+    page.push_str(
+        ".TP\n.B DPG_CONFIG\n\
+         path of the config file\n\
+         .PP\n\
+         Options given on the command line take precedence over environment variables, \
+         environment variables over the profile (\\fB\\-\\-profile\\fR) and the profile over defaults of the config file.\n\
+         .SH FILES\n\
+         .TP\n\
+         .I ~/.config/dpg/config.toml\n\
+         config file: defaults of the options (i.e. \\fBwords = 6\\fR) and profiles (\\fB[profile.<name>]\\fR sections)\n\
+         .SH EXIT STATUS\n\
+         .TP\n.B 0\nsuccess, or help has been shown\n\
+         .TP\n.B 1\nno arguments have been given\n\
+         .TP\n.B 2\ninvalid value of an option or argument\n\
+         .TP\n.B 3\nunknown option\n\
+         .TP\n.B 5\npasswords could not be written\n\
+         .TP\n.B 6\ninvalid config file\n\
+         .SH AUTHOR\n\
+         Maciek Talaska <maciek.talaska@gmail.com>\n\
+         .SH SEE ALSO\n\
+         github.com/MaciekTalaska/dpg\n",
+    );
+    page
+}
+
+fn usage(spec: &OptionSpec) -> String {
+    match spec.value {
+        OptionValue::Flag => format!("\\fB\\-{}\\fR, \\fB\\-\\-{}\\fR", spec.short, escape(spec.long)),
+        OptionValue::Required(value) => format!("\\fB\\-{}\\fR, \\fB\\-\\-{}\\fR \\fI{}\\fR", spec.short, escape(spec.long), value),
+        OptionValue::Optional(value) => format!("\\fB\\-{}\\fR, \\fB\\-\\-{}\\fR[=\\fI{}\\fR]", spec.short, escape(spec.long), value),
+    }
+}
+
+/// Text as roff expects it: backslashes and hyphens escaped, and lines starting with a dot or apostrophe kept as text.
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    match escaped.starts_with('.') || escaped.starts_with('\'') {
+        true => format!("\\&{}", escaped),
+        false => escaped,
+    }
+}
+
+#[cfg(test)]
+mod man_tests {
+    use super::*;
+
+    #[test]
+    fn every_option_and_command_is_described() {
+        let page = page();
+        for spec in OPTION_SPECS.iter() {
+            assert!(page.contains(&format!("\\fB\\-\\-{}\\fR", escape(spec.long))));
+            if !spec.env.is_empty() {
+                assert!(page.contains(&format!(".B {}\n", escape(spec.env))));
+            }
+        }
+        for command in COMMAND_SPECS.iter() {
+            assert!(page.contains(&format!(".TP\n\\fB{}\\fR", command.name)));
+        }
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(escape("-w:3-5"), "\\-w:3\\-5");
+        assert_eq!(escape("C:\\dpg"), "C:\\edpg");
+        assert_eq!(escape(".profile"), "\\&.profile");
+        assert!(page().lines().all(|line| !line.starts_with('\'')));
+    }
+}
@@ -0,0 +1,635 @@
+//! Messages of the command-line utility (help, reports and errors) in the language of the user interface.
+//!
+//! Messages are kept in catalogues (one for every language) and looked up by their keys. Descriptions of options,
+//! commands and attacker models are written in English where they are defined (`OPTION_SPECS`, `COMMAND_SPECS`
+//! and `ATTACKER_MODELS`), so other languages translate them by name.
+//!
+//! Errors are described by `Message`s: key of the message along with its arguments, translated when the error is reported.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use option_parser::{CommandSpec, OptionSpec};
+use strength::AttackerModel;
+
+/// Language of messages, chosen with `--ui-lang`, or taken from the locale (`LC_ALL`, `LC_MESSAGES` or `LANG`).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UiLanguage {
+    #[default]
+    En,
+    Pl,
+}
+
+impl FromStr for UiLanguage {
+    type Err = Message;
+
+    fn from_str(language: &str) -> Result<UiLanguage, Message> {
+        match language.to_lowercase().as_str() {
+            "en" => Ok(UiLanguage::En),
+            "pl" => Ok(UiLanguage::Pl),
+            _ => Err(Message::new("error.unsupported_ui_language", &[&language])),
+        }
+    }
+}
+
+/// Message of the catalogues (i.e. description of an error) along with its arguments, written in the language
+/// of the user interface only when it is shown - so it could be created before the language is known.
+/// `Display` writes it in English.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    key: &'static str,
+    args: Vec<String>,
+    /// message the last argument is taken from (i.e. reason of the error), translated along with this one
+    reason: Option<Box<Message>>,
+}
+
+impl Message {
+    pub fn new(key: &'static str, args: &[&dyn fmt::Display]) -> Message {
+        Message { key, args: args.iter().map(|arg| arg.to_string()).collect(), reason: None }
+    }
+
+    /// Message taking the `reason` as its last argument, i.e. "invalid value of 'words': <reason>".
+    pub fn wrap(key: &'static str, args: &[&dyn fmt::Display], reason: Message) -> Message {
+        Message { reason: Some(Box::new(reason)), ..Message::new(key, args) }
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// The message written in the given language.
+    pub fn in_language(&self, language: UiLanguage) -> String {
+        let reason = self.reason.as_ref().map(|reason| reason.in_language(language));
+        let args = self.args.iter()
+            .chain(reason.as_ref())
+            .map(|arg| arg as &dyn fmt::Display)
+            .collect::<Vec<&dyn fmt::Display>>();
+        language.format(self.key, &args)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.in_language(UiLanguage::En))
+    }
+}
+
+impl Error for Message {}
+
+/// All the languages messages are translated to.
+pub static UI_LANGUAGES: [UiLanguage; 2] = [UiLanguage::En, UiLanguage::Pl];
+
+impl UiLanguage {
+    /// Language of the locale, i.e. `pl_PL.UTF-8` (`None` if messages are not translated to it, or for `C` and `POSIX`).
+    pub fn from_locale(locale: &str) -> Option<UiLanguage> {
+        let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+        match language {
+            "C" | "POSIX" => None,
+            language => language.parse().ok(),
+        }
+    }
+
+    /// Language of the locale set by the first (non-empty) of `LC_ALL`, `LC_MESSAGES` and `LANG`, English by default.
+    pub fn from_env(env: &HashMap<String, String>) -> UiLanguage {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|variable| env.get(*variable))
+            .find(|locale| !locale.is_empty())
+            .and_then(|locale| UiLanguage::from_locale(locale))
+            .unwrap_or_default()
+    }
+
+    /// Language requested on the command line (`--ui-lang pl`, `--ui-lang=pl`, `-g:pl`, `-g pl` or `-gpl`), or taken from the locale.
+    /// It has to be known before the command line is parsed, so errors of parsing are reported in that language.
+    /// The option is recognized by the parser's own rules (`option_parser::ui_language_option`), but invalid values
+    /// are skipped here: they are reported by the parser.
+    pub fn select(args: &[String], env: &HashMap<String, String>) -> UiLanguage {
+        let mut args = args.iter().skip(1).take_while(|arg| arg.as_str() != "--").peekable();
+        let mut requested = None;
+        while let Some(arg) = args.next() {
+            let value = match ::option_parser::ui_language_option(arg) {
+                Some(None) => args.next_if(|next| ::option_parser::is_option_value(next)).map(|value| value.as_str()),
+                value => value.flatten(),
+            };
+            if let Some(language) = value.and_then(|value| value.parse().ok()) {
+                requested = Some(language);
+            }
+        }
+        requested.unwrap_or_else(|| UiLanguage::from_env(env))
+    }
+
+    fn catalogue(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            UiLanguage::En => &ENGLISH,
+            UiLanguage::Pl => &POLISH,
+        }
+    }
+
+    /// Message of the given key; English one if the message has not been translated.
+    pub fn text(self, key: &str) -> &'static str {
+        let find = |catalogue: &'static [(&'static str, &'static str)]| {
+            catalogue.iter().find(|entry| entry.0 == key).map(|entry| entry.1)
+        };
+        find(self.catalogue())
+            .or_else(|| find(&ENGLISH))
+            .unwrap_or_else(|| panic!("message '{}' is not defined", key))
+    }
+
+    /// Message of the given key with placeholders replaced with arguments: `{}` takes the next argument, `{1}` the second one.
+    pub fn format(self, key: &str, args: &[&dyn fmt::Display]) -> String {
+        let mut text = self.text(key);
+        let mut formatted = String::new();
+        let mut next = 0;
+        while let Some(start) = text.find('{') {
+            formatted.push_str(&text[..start]);
+            let end = match text[start..].find('}') {
+                Some(end) => start + end,
+                None => break,
+            };
+            let index = match &text[start + 1..end] {
+                "" => {
+                    next += 1;
+                    Some(next - 1)
+                }
+                index => index.parse::<usize>().ok(),
+            };
+            match index.and_then(|index| args.get(index)) {
+                Some(arg) => formatted.push_str(&arg.to_string()),
+                None => formatted.push_str(&text[start..=end]),
+            }
+            text = &text[end + 1..];
+        }
+        formatted.push_str(text);
+        formatted
+    }
+
+    /// Plural form (`one`, `few` or `many`) used with the number - message keys of countable things end with it.
+    pub fn plural(self, number: u64) -> &'static str {
+        match (self, number) {
+            (_, 1) => "one",
+            (UiLanguage::En, _) => "many",
+            (UiLanguage::Pl, number) if (2..=4).contains(&(number % 10)) && !(12..=14).contains(&(number % 100)) => "few",
+            (UiLanguage::Pl, _) => "many",
+        }
+    }
+
+    /// Description of the option (as shown in help).
+    pub fn option_help(self, spec: &OptionSpec) -> &'static str {
+        self.translate(spec.long, spec.help, &POLISH_OPTIONS)
+    }
+
+    /// Description of the command.
+    pub fn command_about(self, command: &CommandSpec) -> &'static str {
+        self.translate(command.name, command.about, &POLISH_COMMANDS)
+    }
+
+    /// Name of the attacker model.
+    pub fn attacker_name(self, attacker: &AttackerModel) -> &'static str {
+        self.translate(attacker.name, attacker.name, &POLISH_ATTACKERS)
+    }
+
+    fn translate(self, name: &str, english: &'static str, polish: &'static [(&'static str, &'static str)]) -> &'static str {
+        match self {
+            UiLanguage::En => english,
+            UiLanguage::Pl => polish.iter().find(|entry| entry.0 == name).map_or(english, |entry| entry.1),
+        }
+    }
+}
+
+#[rustfmt::skip]
+static ENGLISH: [(&str, &str); 125] = [
+    ("help.header",                     "dpg - diceware password generator\n\
+                                         author: Maciek Talaska <maciek.talaska@gmail.com>\n\
+                                         source: github.com/MaciekTalaska/dpg\n\n"),
+    ("help.usage",                      "usage: dpg [command] [options]\n\ncommands:\n"),
+    ("help.gen_options",                "\noptions (gen):\n"),
+    ("help.values",                     "\nvalues could be given as -w:5, -w 5, -w5, --words 5 or --words=5\n"),
+    ("help.other_commands",             "run 'dpg help <command>' for help on other commands\n"),
+    ("help.command",                    "dpg {0} - {1}\n\nusage: dpg {0} [options]{2}\n\noptions:\n"),
+    ("help.command_values",             "\nvalues could be given as -{0}:<{1}>, -{0} <{1}>, --{2} <{1}> or --{2}=<{1}>\n"),
+    ("error",                           "error: {}"),
+    ("error.help",                      "help requested"),
+    ("error.no_arguments",              "insufficient parameters. Type 'dpg -h' for help."),
+    ("error.unknown_option",            "unknown option: '{}'"),
+    ("error.unknown_option_suggestion", "unknown option: '{}' (did you mean '{}'?)"),
+    ("error.output",                    "unable to write passwords: {}"),
+    ("error.stdin",                     "unable to read standard input: {}"),
+    ("error.unknown_command",           "unknown command: '{}'\n  commands: {} (options need a '-' prefix)"),
+    ("error.unexpected_argument",       "unrecognized option: {}\n  are you missing a '-' prefix?"),
+    ("error.flag_value",                "option '{}' does not take a value"),
+    ("error.missing_value",             "option '{}' requires a value: <{}>"),
+    ("error.env_flag",                  "invalid value of {}: '{}' (expected 1 or 0)"),
+    ("error.env_value",                 "invalid value of {}: {}"),
+    ("error.words_required",            "'-w' (or '-e', '-t') is required!"),
+    ("error.roll_words_required",       "'-w' is required!"),
+    ("error.word_count",                "password should consist of at least {} and max {} words"),
+    ("error.word_count_range",          "password should consist of at least {} and max {} words (range should be given as <min>-<max>)"),
+    ("error.word_range",                "range of words should be given as <min>-<max>, where {} <= min <= max <= {}"),
+    ("error.password_count",            "number of passwords should be a non-negative number (0 to generate passwords until the output is closed)"),
+    ("error.unsupported_language",      "language: '{}' is not supported!"),
+    ("error.unsupported_ui_language",   "unsupported language of messages: '{}' (supported languages: en, pl)"),
+    ("error.unknown_case",              "unknown capitalization style: '{}'"),
+    ("error.supported_cases",           "{}\n  supported styles: lower, upper, title, camel, word, letter"),
+    ("error.unknown_format",            "unknown output format: '{}'"),
+    ("error.supported_formats",         "{}\n  supported formats: text, plain, json, ndjson, csv"),
+    ("error.unsupported_shell",         "unsupported shell: '{}' (supported shells: bash, zsh, fish)"),
+    ("error.one_shell",                 "name of exactly one shell is needed: bash, zsh or fish"),
+    ("error.threads",                   "number of threads should be a positive number"),
+    ("error.max_characters",            "maximal password length should be a positive number of characters"),
+    ("error.empty_separator_set",       "separator set should contain at least one character"),
+    ("error.template_conflict",         "'-{}' can not be used together with template ('-t')"),
+    ("error.words_and_entropy",         "'-w' and '-e' can not be used together"),
+    ("error.entropy",                   "target entropy should be a positive number of bits"),
+    ("error.entropy_unreachable",       "{} bits of entropy can not be reached using at most {} words"),
+    ("error.nothing_to_look_up",        "nothing to look up: give dice rolls (i.e. 11111) or words"),
+    ("error.one_password",              "only one password could be checked at once"),
+    ("error.unlimited_clipboard",       "unlimited number of passwords could not be copied to clipboard"),
+    ("error.unlimited_unique",          "passwords of an unlimited stream could not be checked for repetitions"),
+    ("error.distinct_words",            "list contains only {} distinct words, {} are needed"),
+    ("error.distinct_passwords",        "there are not enough possible passwords to generate {} distinct ones"),
+    ("error.invalid_dice_roll",         "invalid dice roll: '{}' (expected values from 1 to 6)"),
+    ("error.dice_rolls_count",          "not enough dice rolls to insert extra character: at least 4 are needed, and rolls greater than {} are repeated when choosing one of {} words"),
+    ("error.dice_rolls_run_out",        "not enough dice rolls to insert extra character: rolls greater than the length of the chosen word are repeated"),
+    ("error.dice_rolls_one_password",   "physical dice rolls could be used to insert extra character into a single password only (-p 1)"),
+    ("error.rolls_per_word",            "'{}': {} dice rolls are needed to pick a word from the '{}' list"),
+    ("error.rolls_digits",              "'{}': dice rolls should be given as digits from 1 to 6"),
+    ("error.policy_not_met",            "password policy could not be met: {}"),
+    ("error.policy.unknown_rule",       "unknown policy rule: '{}'"),
+    ("error.policy.number",             "policy rule '{}' requires a number, got: '{}'"),
+    ("error.policy.separator",          "separator '{}' contains forbidden character"),
+    ("error.policy.extra",              "extra character could be one of the forbidden characters"),
+    ("error.policy.repeat",             "at least 1 character in a row has to be allowed"),
+    ("error.policy.separator_repeats",  "separator '{}' could create repeated characters"),
+    ("error.policy.no_words",           "none of the words from the selected list complies with the policy"),
+    ("error.policy.distinct_words",     "only {} words from the selected list comply with the policy, {} distinct words are needed"),
+    ("error.policy.max_length",         "maximal length of {} characters is too short"),
+    ("error.policy.length",             "no password consisting of {} words fits the length limits"),
+    ("error.policy.restrictive",        "length limits are too restrictive for the selected list"),
+    ("error.policy.no_lowercase",       "capitalization style {} leaves no lowercase letters"),
+    ("error.policy.no_digits",          "policy requires a digit but forbids all of them"),
+    ("error.policy.no_symbols",         "policy requires a symbol but forbids all of them"),
+    ("error.template.unclosed",         "missing '}' in template"),
+    ("error.template.unexpected",       "unexpected '}' in template"),
+    ("error.template.count",            "invalid count in template placeholder: '{}'"),
+    ("error.template.placeholder",      "unknown template placeholder: '{}'"),
+    ("error.config.read",               "{}: unable to read config file: {}"),
+    ("error.config.location",           "{}:{}: {}"),
+    ("error.config.no_config",          "profile '{}' could not be used: config file has not been found"),
+    ("error.config.profile",            "{}: profile '{}' is not defined (available profiles: {})"),
+    ("error.config.profiles",           "{}"),
+    ("error.config.no_profiles",        "none"),
+    ("error.config.repeated_key",       "'{}' is set more than once"),
+    ("error.config.section",            "section should be given as [profile.<name>]"),
+    ("error.config.unknown_section",    "unknown section: '[{}]' (expected [profile.<name>])"),
+    ("error.config.repeated_profile",   "profile '{}' is defined more than once"),
+    ("error.config.key_value",          "expected <key> = <value>"),
+    ("error.config.unknown_key",        "unknown key: '{}'"),
+    ("error.config.boolean",            "'{}' should be true or false"),
+    ("error.config.string_or_number",   "'{}' should be a string or a number"),
+    ("error.config.invalid_value",      "invalid value of '{}': {}"),
+    ("error.config.missing_value",      "value is missing"),
+    ("error.config.unquoted",           "invalid value: {} (strings have to be quoted)"),
+    ("error.config.unterminated",       "unterminated string"),
+    ("error.config.after_string",       "unexpected text after string"),
+    ("error.config.escape",             "unsupported escape sequence: \\{}"),
+    ("warning.ambiguous_words",         "warning: words are not separated, so {} words being prefixes of other words (i.e. '{}') \
+                                         are not used to avoid ambiguous passwords"),
+    ("generated",                       "generated password(s):\n"),
+    ("settings",                        "effective settings:\n"),
+    ("source.default",                  "default"),
+    ("source.config",                   "config file {}:{}"),
+    ("source.profile",                  "profile '{}' {}:{}"),
+    ("source.environment",              "environment variable {}"),
+    ("source.command_line",             "command line"),
+    ("lists",                           "available word lists:\n"),
+    ("lists.list",                      "  {}{} words, {} dice per word, {} bits per word\n"),
+    ("lookup.not_on_list",              "'{}' is not on the '{}' list"),
+    ("check.no_password",               "no password to check"),
+    ("check.unknown_words",             "password does not consist of words of any list (only separated words could be recognized)"),
+    ("check.report",                    "word list: {}\nwords: {}\n\n{}"),
+    ("roll.enter",                      "enter {} dice rolls ({} dice each, i.e. {}):"),
+    ("roll.count",                      "{} dice rolls are needed (one for every word), {} given"),
+    ("strength",                        "entropy: {} bits\nestimated time to crack:\n"),
+    ("duration.less_than_second",       "less than a second"),
+    ("duration.seconds.one",            "{} second"),
+    ("duration.seconds.few",            "{} seconds"),
+    ("duration.seconds.many",           "{} seconds"),
+    ("duration.minutes.one",            "{} minute"),
+    ("duration.minutes.few",            "{} minutes"),
+    ("duration.minutes.many",           "{} minutes"),
+    ("duration.hours.one",              "{} hour"),
+    ("duration.hours.few",              "{} hours"),
+    ("duration.hours.many",             "{} hours"),
+    ("duration.days.one",               "{} day"),
+    ("duration.days.few",               "{} days"),
+    ("duration.days.many",              "{} days"),
+    ("duration.years.one",              "{} year"),
+    ("duration.years.few",              "{} years"),
+    ("duration.years.many",             "{} years"),
+];
+
+#[rustfmt::skip]
+static POLISH: [(&str, &str); 125] = [
+    ("help.header",                     "dpg - generator haseł diceware\n\
+                                         autor: Maciek Talaska <maciek.talaska@gmail.com>\n\
+                                         źródło: github.com/MaciekTalaska/dpg\n\n"),
+    ("help.usage",                      "użycie: dpg [polecenie] [opcje]\n\npolecenia:\n"),
+    ("help.gen_options",                "\nopcje (gen):\n"),
+    ("help.values",                     "\nwartości można podać jako -w:5, -w 5, -w5, --words 5 lub --words=5\n"),
+    ("help.other_commands",             "pomoc do pozostałych poleceń: 'dpg help <polecenie>'\n"),
+    ("help.command",                    "dpg {0} - {1}\n\nużycie: dpg {0} [opcje]{2}\n\nopcje:\n"),
+    ("help.command_values",             "\nwartości można podać jako -{0}:<{1}>, -{0} <{1}>, --{2} <{1}> lub --{2}=<{1}>\n"),
+    ("error",                           "błąd: {}"),
+    ("error.help",                      "wyświetlenie pomocy"),
+    ("error.no_arguments",              "za mało parametrów. Wpisz 'dpg -h', aby wyświetlić pomoc."),
+    ("error.unknown_option",            "nieznana opcja: '{}'"),
+    ("error.unknown_option_suggestion", "nieznana opcja: '{}' (czy chodziło o '{}'?)"),
+    ("error.output",                    "nie udało się zapisać haseł: {}"),
+    ("error.stdin",                     "nie udało się odczytać standardowego wejścia: {}"),
+    ("error.unknown_command",           "nieznane polecenie: '{}'\n  polecenia: {} (opcje muszą zaczynać się od '-')"),
+    ("error.unexpected_argument",       "nierozpoznana opcja: {}\n  czy brakuje prefiksu '-'?"),
+    ("error.flag_value",                "opcja '{}' nie przyjmuje wartości"),
+    ("error.missing_value",             "opcja '{}' wymaga wartości: <{}>"),
+    ("error.env_flag",                  "nieprawidłowa wartość {}: '{}' (oczekiwano 1 lub 0)"),
+    ("error.env_value",                 "nieprawidłowa wartość {}: {}"),
+    ("error.words_required",            "wymagana jest opcja '-w' (lub '-e', '-t')!"),
+    ("error.roll_words_required",       "wymagana jest opcja '-w'!"),
+    ("error.word_count",                "hasło musi składać się z co najmniej {} i najwyżej {} słów"),
+    ("error.word_count_range",          "hasło musi składać się z co najmniej {} i najwyżej {} słów (zakres należy podać jako <min>-<max>)"),
+    ("error.word_range",                "zakres słów należy podać jako <min>-<max>, gdzie {} <= min <= max <= {}"),
+    ("error.password_count",            "liczba haseł musi być liczbą nieujemną (0, aby generować hasła aż do zamknięcia wyjścia)"),
+    ("error.unsupported_language",      "język: '{}' nie jest obsługiwany!"),
+    ("error.unsupported_ui_language",   "nieobsługiwany język komunikatów: '{}' (dostępne języki: en, pl)"),
+    ("error.unknown_case",              "nieznany styl wielkości liter: '{}'"),
+    ("error.supported_cases",           "{}\n  dostępne style: lower, upper, title, camel, word, letter"),
+    ("error.unknown_format",            "nieznany format wyjścia: '{}'"),
+    ("error.supported_formats",         "{}\n  dostępne formaty: text, plain, json, ndjson, csv"),
+    ("error.unsupported_shell",         "nieobsługiwana powłoka: '{}' (dostępne powłoki: bash, zsh, fish)"),
+    ("error.one_shell",                 "potrzebna jest nazwa dokładnie jednej powłoki: bash, zsh lub fish"),
+    ("error.threads",                   "liczba wątków musi być liczbą dodatnią"),
+    ("error.max_characters",            "maksymalna długość hasła musi być dodatnią liczbą znaków"),
+    ("error.empty_separator_set",       "zbiór separatorów musi zawierać co najmniej jeden znak"),
+    ("error.template_conflict",         "opcji '-{}' nie można używać razem z szablonem ('-t')"),
+    ("error.words_and_entropy",         "opcji '-w' i '-e' nie można używać razem"),
+    ("error.entropy",                   "docelowa entropia musi być dodatnią liczbą bitów"),
+    ("error.entropy_unreachable",       "entropii {} bitów nie można osiągnąć, używając najwyżej {} słów"),
+    ("error.nothing_to_look_up",        "nie ma czego wyszukać: podaj wyniki rzutów kośćmi (np. 11111) lub słowa"),
+    ("error.one_password",              "naraz można sprawdzić tylko jedno hasło"),
+    ("error.unlimited_clipboard",       "nieograniczonej liczby haseł nie można skopiować do schowka"),
+    ("error.unlimited_unique",          "nie można sprawdzić powtórzeń w nieograniczonym strumieniu haseł"),
+    ("error.distinct_words",            "lista zawiera tylko {} różnych słów, potrzeba {}"),
+    ("error.distinct_passwords",        "nie ma wystarczająco wielu możliwych haseł, aby wygenerować {} różnych"),
+    ("error.invalid_dice_roll",         "nieprawidłowy wynik rzutu kością: '{}' (oczekiwano wartości od 1 do 6)"),
+    ("error.dice_rolls_count",          "za mało wyników rzutów kośćmi, aby wstawić dodatkowy znak: potrzeba co najmniej 4, a rzuty większe niż {} są powtarzane przy wyborze jednego z {} słów"),
+    ("error.dice_rolls_run_out",        "za mało wyników rzutów kośćmi, aby wstawić dodatkowy znak: rzuty większe niż długość wybranego słowa są powtarzane"),
+    ("error.dice_rolls_one_password",   "wyników rzutów kośćmi można użyć do wstawienia dodatkowego znaku tylko do jednego hasła (-p 1)"),
+    ("error.rolls_per_word",            "'{0}': do wybrania słowa z listy '{2}' potrzeba wyników {1} rzutów kośćmi"),
+    ("error.rolls_digits",              "'{}': wyniki rzutów kośćmi należy podać jako cyfry od 1 do 6"),
+    ("error.policy_not_met",            "nie można spełnić zasad haseł: {}"),
+    ("error.policy.unknown_rule",       "nieznana reguła zasad haseł: '{}'"),
+    ("error.policy.number",             "reguła '{}' wymaga liczby, podano: '{}'"),
+    ("error.policy.separator",          "separator '{}' zawiera zabroniony znak"),
+    ("error.policy.extra",              "dodatkowy znak mógłby być jednym z zabronionych znaków"),
+    ("error.policy.repeat",             "musi być dozwolony co najmniej 1 znak z rzędu"),
+    ("error.policy.separator_repeats",  "separator '{}' mógłby tworzyć powtórzone znaki"),
+    ("error.policy.no_words",           "żadne słowo z wybranej listy nie spełnia zasad haseł"),
+    ("error.policy.distinct_words",     "tylko {} słów z wybranej listy spełnia zasady haseł, potrzeba {} różnych słów"),
+    ("error.policy.max_length",         "maksymalna długość {} znaków jest za mała"),
+    ("error.policy.length",             "żadne hasło składające się z {} słów nie mieści się w limitach długości"),
+    ("error.policy.restrictive",        "limity długości są zbyt restrykcyjne dla wybranej listy"),
+    ("error.policy.no_lowercase",       "styl wielkości liter {} nie pozostawia małych liter"),
+    ("error.policy.no_digits",          "zasady wymagają cyfry, ale zabraniają wszystkich cyfr"),
+    ("error.policy.no_symbols",         "zasady wymagają symbolu, ale zabraniają wszystkich symboli"),
+    ("error.template.unclosed",         "brak '}' w szablonie"),
+    ("error.template.unexpected",       "nieoczekiwany znak '}' w szablonie"),
+    ("error.template.count",            "nieprawidłowa liczba w polu szablonu: '{}'"),
+    ("error.template.placeholder",      "nieznane pole szablonu: '{}'"),
+    ("error.config.read",               "{}: nie udało się odczytać pliku konfiguracyjnego: {}"),
+    ("error.config.location",           "{}:{}: {}"),
+    ("error.config.no_config",          "nie można użyć profilu '{}': nie znaleziono pliku konfiguracyjnego"),
+    ("error.config.profile",            "{}: profil '{}' nie jest zdefiniowany (dostępne profile: {})"),
+    ("error.config.profiles",           "{}"),
+    ("error.config.no_profiles",        "brak"),
+    ("error.config.repeated_key",       "'{}' ustawiono więcej niż raz"),
+    ("error.config.section",            "sekcję należy podać jako [profile.<nazwa>]"),
+    ("error.config.unknown_section",    "nieznana sekcja: '[{}]' (oczekiwano [profile.<nazwa>])"),
+    ("error.config.repeated_profile",   "profil '{}' zdefiniowano więcej niż raz"),
+    ("error.config.key_value",          "oczekiwano <klucz> = <wartość>"),
+    ("error.config.unknown_key",        "nieznany klucz: '{}'"),
+    ("error.config.boolean",            "'{}' musi mieć wartość true lub false"),
+    ("error.config.string_or_number",   "'{}' musi być napisem lub liczbą"),
+    ("error.config.invalid_value",      "nieprawidłowa wartość '{}': {}"),
+    ("error.config.missing_value",      "brak wartości"),
+    ("error.config.unquoted",           "nieprawidłowa wartość: {} (napisy muszą być w cudzysłowie)"),
+    ("error.config.unterminated",       "niezakończony napis"),
+    ("error.config.after_string",       "nieoczekiwany tekst po napisie"),
+    ("error.config.escape",             "nieobsługiwana sekwencja ucieczki: \\{}"),
+    ("warning.ambiguous_words",         "ostrzeżenie: słowa nie są rozdzielone, więc słowa będące przedrostkami innych słów \
+                                         (liczba: {}, np. '{}') nie są używane, aby hasła były jednoznaczne"),
+    ("generated",                       "wygenerowane hasła:\n"),
+    ("settings",                        "obowiązujące ustawienia:\n"),
+    ("source.default",                  "wartość domyślna"),
+    ("source.config",                   "plik konfiguracyjny {}:{}"),
+    ("source.profile",                  "profil '{}' {}:{}"),
+    ("source.environment",              "zmienna środowiskowa {}"),
+    ("source.command_line",             "wiersz poleceń"),
+    ("lists",                           "dostępne listy słów:\n"),
+    ("lists.list",                      "  {}słów: {}, kości na słowo: {}, bitów na słowo: {}\n"),
+    ("lookup.not_on_list",              "słowa '{}' nie ma na liście '{}'"),
+    ("check.no_password",               "brak hasła do sprawdzenia"),
+    ("check.unknown_words",             "hasło nie składa się ze słów żadnej listy (rozpoznawane są tylko rozdzielone słowa)"),
+    ("check.report",                    "lista słów: {}\nliczba słów: {}\n\n{}"),
+    ("roll.enter",                      "podaj wyniki rzutów kośćmi, po jednym na słowo (liczba słów: {}, kości na słowo: {}, np. {}):"),
+    ("roll.count",                      "potrzeba jednego wyniku rzutów na każde słowo (liczba słów: {}), podano: {}"),
+    ("strength",                        "entropia (w bitach): {}\nszacowany czas złamania hasła:\n"),
+    ("duration.less_than_second",       "mniej niż sekunda"),
+    ("duration.seconds.one",            "{} sekunda"),
+    ("duration.seconds.few",            "{} sekundy"),
+    ("duration.seconds.many",           "{} sekund"),
+    ("duration.minutes.one",            "{} minuta"),
+    ("duration.minutes.few",            "{} minuty"),
+    ("duration.minutes.many",           "{} minut"),
+    ("duration.hours.one",              "{} godzina"),
+    ("duration.hours.few",              "{} godziny"),
+    ("duration.hours.many",             "{} godzin"),
+    ("duration.days.one",               "{} dzień"),
+    ("duration.days.few",               "{} dni"),
+    ("duration.days.many",              "{} dni"),
+    ("duration.years.one",              "{} rok"),
+    ("duration.years.few",              "{} lata"),
+    ("duration.years.many",             "{} lat"),
+];
+
+/// Descriptions of options (by long name), aligned the same way as the English ones.
+#[rustfmt::skip]
+static POLISH_OPTIONS: [(&str, &str); 22] = [
+    ("language",         "język listy słów (en lub pl)          [domyślnie: en]"),
+    ("words",            "długość hasła (w słowach)             [zakres: 1-255]\n\
+                          lub <min>-<max> dla losowej długości"),
+    ("count",            "liczba haseł do wygenerowania         [domyślnie: 1]\n\
+                          (-p:0 aż do zamknięcia wyjścia)"),
+    ("entropy",          "minimalna entropia hasła (w bitach)   [zamiast -w]"),
+    ("separator",        "znaki rozdzielające słowa             [domyślnie: '-']\n\
+                          (-s: bez separatora)"),
+    ("separator-set",    "zbiór separatorów, losowanych między  [zamiast -s]\n\
+                          każdą parą słów (np. -j:-_.! lub -j:digits)"),
+    ("case",             "wielkość liter: lower, upper, title,  [domyślnie: lower]\n\
+                          camel, word lub letter (losowo)"),
+    ("extra",            "wstaw dodatkowy znak (metoda Reinholda),\n\
+                          opcjonalnie wg rzutów kośćmi (np. -x:3425)"),
+    ("max-chars",        "maksymalna długość hasła (w znakach)"),
+    ("template",         "szablon hasła, np. -t:w.w.w.d2.s lub  [zamiast -w]\n\
+                          -t:{word}-{word}{digits:3}{symbol}"),
+    ("policy",           "zasady, które hasło musi spełniać (oddzielone przecinkami):\n\
+                          min=<n>, max=<n>, upper, lower, digit, symbol,\n\
+                          forbid=<znaki>, nospace, repeat=<n>"),
+    ("unique-words",     "słowa nie powtarzają się w haśle"),
+    ("unique-passwords", "hasła nie powtarzają się w serii"),
+    ("parallel",         "generuj hasła w kilku wątkach         [domyślnie: liczba procesorów]"),
+    ("unordered",        "wypisuj hasła generowane równolegle,\n\
+                          gdy tylko są gotowe (nie po kolei)"),
+    ("format",           "format wyjścia: text, plain (hasła),  [domyślnie: text]\n\
+                          json, ndjson lub csv"),
+    ("clipboard",        "skopiuj hasła do schowka"),
+    ("dice",             "symuluj rzucanie kośćmi (wolniej)"),
+    ("profile",          "użyj ustawień profilu z pliku konfiguracyjnego"),
+    ("show-config",      "pokaż obowiązujące ustawienia i ich źródła"),
+    ("ui-lang",          "język komunikatów (en lub pl)         [domyślnie: wg LANG]"),
+    ("help",             "ta pomoc"),
+];
+
+#[rustfmt::skip]
+static POLISH_COMMANDS: [(&str, &str); 7] = [
+    ("gen",         "generuje hasła (polecenie domyślne)"),
+    ("lists",       "pokazuje dostępne listy słów"),
+    ("lookup",      "znajduje słowa dla rzutów kośćmi (np. 11111) i rzuty kośćmi dla słów"),
+    ("check",       "szacuje siłę hasła diceware (czytanego ze standardowego wejścia, jeśli nie zostało podane)"),
+    ("roll",        "składa hasło z rzutów prawdziwymi kośćmi (czytanych ze standardowego wejścia)"),
+    ("completions", "wypisuje skrypt uzupełniania poleceń dla powłoki (bash, zsh lub fish)"),
+    ("man",         "wypisuje stronę podręcznika (roff)"),
+];
+
+#[rustfmt::skip]
+static POLISH_ATTACKERS: [(&str, &str); 3] = [
+    ("online, throttled (100 guesses/hour)", "online, z limitem (100 prób/godz.)"),
+    ("offline, slow hash (1e4 guesses/s)",   "offline, wolny skrót (1e4 prób/s)"),
+    ("offline, fast hash (1e10 guesses/s)",  "offline, szybki skrót (1e10 prób/s)"),
+];
+
+#[cfg(test)]
+mod messages_tests {
+    use super::*;
+    use option_parser::{COMMAND_SPECS, OPTION_SPECS};
+    use strength::ATTACKER_MODELS;
+
+    fn placeholders(text: &str) -> usize {
+        text.matches('{').count()
+    }
+
+    #[test]
+    fn every_message_exists_in_every_catalogue() {
+        for language in UI_LANGUAGES.iter() {
+            let catalogue = language.catalogue();
+            for &(key, english) in ENGLISH.iter() {
+                let translated = catalogue.iter().find(|entry| entry.0 == key);
+                assert!(translated.is_some(), "{:?}: message '{}' is missing", language, key);
+                assert_eq!(placeholders(translated.unwrap().1), placeholders(english), "{:?}: placeholders of '{}'", language, key);
+            }
+            for &(key, _) in catalogue.iter() {
+                assert!(ENGLISH.iter().any(|entry| entry.0 == key), "{:?}: unknown message '{}'", language, key);
+                assert_eq!(catalogue.iter().filter(|entry| entry.0 == key).count(), 1, "{:?}: '{}' is repeated", language, key);
+            }
+        }
+    }
+
+    #[test]
+    fn every_option_command_and_attacker_is_translated() {
+        for spec in OPTION_SPECS.iter() {
+            assert!(POLISH_OPTIONS.iter().any(|entry| entry.0 == spec.long), "option '{}' is not translated", spec.long);
+            assert_eq!(UiLanguage::Pl.option_help(spec).lines().count(), spec.help.lines().count(), "{}", spec.long);
+        }
+        for command in COMMAND_SPECS.iter() {
+            assert!(POLISH_COMMANDS.iter().any(|entry| entry.0 == command.name), "command '{}' is not translated", command.name);
+        }
+        for attacker in ATTACKER_MODELS.iter() {
+            assert!(POLISH_ATTACKERS.iter().any(|entry| entry.0 == attacker.name), "'{}' is not translated", attacker.name);
+        }
+        assert_eq!(POLISH_OPTIONS.len(), OPTION_SPECS.len());
+        assert_eq!(POLISH_COMMANDS.len(), COMMAND_SPECS.len());
+    }
+
+    #[test]
+    fn placeholders_are_replaced_with_arguments() {
+        assert_eq!(UiLanguage::En.format("error.unknown_option_suggestion", &[&"--wrods", &"--words"]),
+                   "unknown option: '--wrods' (did you mean '--words'?)");
+        assert_eq!(UiLanguage::Pl.format("help.command_values", &[&'w', &"number", &"words"]),
+                   "\nwartości można podać jako -w:<number>, -w <number>, --words <number> lub --words=<number>\n");
+        assert_eq!(UiLanguage::En.format("error", &[]), "error: {}");
+    }
+
+    #[test]
+    fn messages_are_written_in_language_given_when_shown() {
+        let reason = Message::new("error.policy.unknown_rule", &[&"emoji"]);
+        let message = Message::wrap("error.policy_not_met", &[], reason);
+        assert_eq!(message.key(), "error.policy_not_met");
+        assert_eq!(message.to_string(), "password policy could not be met: unknown policy rule: 'emoji'");
+        assert_eq!(message.in_language(UiLanguage::Pl), "nie można spełnić zasad haseł: nieznana reguła zasad haseł: 'emoji'");
+        assert_eq!(Message::new("error.rolls_per_word", &[&"11", &5, &"pl"]).in_language(UiLanguage::Pl),
+                   "'11': do wybrania słowa z listy 'pl' potrzeba wyników 5 rzutów kośćmi");
+        assert_eq!("de".parse::<UiLanguage>().unwrap_err().key(), "error.unsupported_ui_language");
+    }
+
+    #[test]
+    fn language_is_taken_from_locale() {
+        assert_eq!(UiLanguage::from_locale("pl_PL.UTF-8"), Some(UiLanguage::Pl));
+        assert_eq!(UiLanguage::from_locale("en_GB"), Some(UiLanguage::En));
+        assert_eq!(UiLanguage::from_locale("C.UTF-8"), None);
+        assert_eq!(UiLanguage::from_locale("de_DE@euro"), None);
+
+        let mut env = HashMap::new();
+        assert_eq!(UiLanguage::from_env(&env), UiLanguage::En);
+        env.insert(s!("LANG"), s!("pl_PL.UTF-8"));
+        assert_eq!(UiLanguage::from_env(&env), UiLanguage::Pl);
+        env.insert(s!("LC_MESSAGES"), s!("en_US.UTF-8"));
+        assert_eq!(UiLanguage::from_env(&env), UiLanguage::En);
+        env.insert(s!("LC_ALL"), s!(""));
+        assert_eq!(UiLanguage::from_env(&env), UiLanguage::En);
+    }
+
+    #[test]
+    fn command_line_takes_precedence_over_locale() {
+        let mut env = HashMap::new();
+        env.insert(s!("LANG"), s!("pl_PL.UTF-8"));
+        let select = |args: &[&str]| UiLanguage::select(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>(), &env);
+
+        assert_eq!(select(&["dpg", "-w:3"]), UiLanguage::Pl);
+        assert_eq!(select(&["dpg", "-w:3", "--ui-lang", "en"]), UiLanguage::En);
+        assert_eq!(select(&["dpg", "lists", "--ui-lang=EN"]), UiLanguage::En);
+        assert_eq!(select(&["dpg", "-g:en"]), UiLanguage::En);
+        assert_eq!(select(&["dpg", "-g:xx"]), UiLanguage::Pl);
+        assert_eq!(select(&["dpg", "check", "--", "-g:en"]), UiLanguage::Pl);
+    }
+
+    #[test]
+    fn language_is_selected_as_the_option_would_be_parsed() {
+        let select = |args: &[&str]| UiLanguage::select(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>(), &HashMap::new());
+
+        assert_eq!(select(&["dpg", "-w:3", "-cg:pl"]), UiLanguage::Pl);
+        assert_eq!(select(&["dpg", "-w:3", "-G", "pl"]), UiLanguage::Pl);
+        assert_eq!(select(&["dpg", "-w:3", "--UI-LANG=pl"]), UiLanguage::Pl);
+        // values of other options, and options following the one requiring a value, are not taken as the language
+        assert_eq!(select(&["dpg", "-w:3", "-sg", "pl"]), UiLanguage::En);
+        assert_eq!(select(&["dpg", "-g", "-w:3", "pl"]), UiLanguage::En);
+    }
+
+    #[test]
+    fn plural_forms_depend_on_number() {
+        assert_eq!(UiLanguage::En.plural(1), "one");
+        assert_eq!(UiLanguage::En.plural(3), "many");
+        assert_eq!(UiLanguage::Pl.plural(1), "one");
+        assert_eq!(UiLanguage::Pl.plural(22), "few");
+        assert_eq!(UiLanguage::Pl.plural(12), "many");
+        assert_eq!(UiLanguage::Pl.plural(25), "many");
+    }
+}
@@ -1,32 +1,218 @@
 use std::collections::HashMap;
+use std::fmt;
 
-#[cfg(not(debug_assertions))]
-use std::process::exit;
-#[cfg(debug_assertions)]
-fn exit(exit_code: i32) {
-    eprintln!("exit_code: {0}", exit_code);
-    panic!("{0}", exit_code);
+use capitalization::Capitalization;
+use completions::Shell;
+use config::{Config, Setting};
+use error::DpgError;
+use extra_security::ExtraSecurity;
+use messages::{Message, UiLanguage};
+use output::OutputFormat;
+use policy::Policy;
+use template::Template;
+
+static DEFAULT_SEPARATOR: &str = "-";
+static DEFAULT_PASSWORD_COUNT: usize = 1;
+
+pub static MIN_WORDS_COUNT: usize = 1;
+pub static MAX_WORDS_COUNT: usize = 255;
+/// Number of passwords meaning "generate passwords until the output is closed".
+pub static UNLIMITED_PASSWORD_COUNT: usize = 0;
+
+/// Kind of value a command-line option takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OptionValue {
+    /// option is a flag, it takes no value
+    Flag,
+    /// value is required: `-w 5`, `-w5`, `-w:5`, `--words 5` or `--words=5`
+    Required(&'static str),
+    /// value is optional, so it has to be attached to the option: `-x3425`, `-x:3425` or `--extra=3425`
+    Optional(&'static str),
 }
 
-static ERR_NO_ARGUMENTS: i32 = 1;
-static ERR_ARGUMENT_PARSING: i32 = 2;
-static ERR_UNKNOWN_OPTION: i32 = 3;
-static ERR_TOO_MANY_OPTIONS: i32 = 4;
+/// Values of an option offered by shell completions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Values {
+    /// any value (or no value at all)
+    Any,
+    /// one of the listed values
+    OneOf(&'static [&'static str]),
+    /// name of one of the available word lists (listed by `dpg lists`)
+    WordLists,
+}
 
-static DEFAULT_SEPARATOR: &'static str = "-";
-static DEFAULT_PASSWORD_COUNT: usize = 1;
+/// Definition of a command-line option: help, shell completions and the manual page are generated from it.
+#[rustfmt::skip]
+pub struct OptionSpec {
+    pub short:  char,
+    pub long:   &'static str,
+    pub value:  OptionValue,
+    /// environment variable the value could be taken from (empty if none)
+    pub env:    &'static str,
+    pub values: Values,
+    /// description, continuation lines are separated with '\n'
+    pub help:   &'static str,
+}
 
-static MIN_WORDS_COUNT: usize = 1;
-static MAX_WORDS_COUNT: usize = 255;
-static MAX_PASSWORD_COUNT: usize = 255;
-static MIN_PASSWORD_COUNT: usize = 1;
+#[rustfmt::skip]
+pub static OPTION_SPECS: [OptionSpec; 22] = [
+    OptionSpec { short: 'l', long: "language",          value: OptionValue::Required("language"),
+                 env: "DPG_LANG",             values: Values::WordLists,
+                 help: "language (en or pl)                   [default: en]" },
+    OptionSpec { short: 'w', long: "words",             value: OptionValue::Required("number"),
+                 env: "DPG_WORDS",            values: Values::Any,
+                 help: "password length (in words)            [range: 1-255]\n\
+                        or <min>-<max> for random length" },
+    OptionSpec { short: 'p', long: "count",             value: OptionValue::Required("number"),
+                 env: "DPG_COUNT",            values: Values::Any,
+                 help: "number of passwords to generate       [default: 1]\n\
+                        (-p:0 until the output is closed)" },
+    OptionSpec { short: 'e', long: "entropy",           value: OptionValue::Required("bits"),
+                 env: "DPG_ENTROPY",          values: Values::Any,
+                 help: "minimal password entropy (in bits)    [instead of -w]" },
+    OptionSpec { short: 's', long: "separator",         value: OptionValue::Required("chars"),
+                 env: "DPG_SEPARATOR",        values: Values::Any,
+                 help: "characters to separate words with     [default: '-']\n\
+                        (-s: for no separator)" },
+    OptionSpec { short: 'j', long: "separator-set",     value: OptionValue::Required("chars"),
+                 env: "DPG_SEPARATOR_SET",    values: Values::Any,
+                 help: "set of separators, randomly chosen between  [instead of -s]\n\
+                        each pair of words (i.e. -j:-_.! or -j:digits)" },
+    OptionSpec { short: 'u', long: "case",              value: OptionValue::Required("style"),
+                 env: "DPG_CASE",             values: Values::OneOf(&["lower", "upper", "title", "camel", "word", "letter"]),
+                 help: "capitalization: lower, upper, title,  [default: lower]\n\
+                        camel, word or letter (random)" },
+    OptionSpec { short: 'x', long: "extra",             value: OptionValue::Optional("rolls"),
+                 env: "DPG_EXTRA",            values: Values::Any,
+                 help: "insert extra character (Reinhold's method),\n\
+                        optionally using physical dice rolls (i.e. -x:3425)" },
+    OptionSpec { short: 'm', long: "max-chars",         value: OptionValue::Required("number"),
+                 env: "DPG_MAX_CHARS",        values: Values::Any,
+                 help: "maximal password length (in characters)" },
+    OptionSpec { short: 't', long: "template",          value: OptionValue::Required("template"),
+                 env: "DPG_TEMPLATE",         values: Values::Any,
+                 help: "password template, i.e. -t:w.w.w.d2.s or    [instead of -w]\n\
+                        -t:{word}-{word}{digits:3}{symbol}" },
+    OptionSpec { short: 'r', long: "policy",            value: OptionValue::Required("rules"),
+                 env: "DPG_POLICY",           values: Values::Any,
+                 help: "password policy, comma separated rules:\n\
+                        min=<n>, max=<n>, upper, lower, digit, symbol,\n\
+                        forbid=<chars>, nospace, repeat=<n>" },
+    OptionSpec { short: 'n', long: "unique-words",      value: OptionValue::Flag,
+                 env: "DPG_UNIQUE_WORDS",     values: Values::Any,
+                 help: "words do not repeat within a password" },
+    OptionSpec { short: 'b', long: "unique-passwords",  value: OptionValue::Flag,
+                 env: "DPG_UNIQUE_PASSWORDS", values: Values::Any,
+                 help: "passwords do not repeat within a batch" },
+    OptionSpec { short: 'a', long: "parallel",          value: OptionValue::Optional("threads"),
+                 env: "DPG_PARALLEL",         values: Values::Any,
+                 help: "generate passwords on several threads [default: number of CPUs]" },
+    OptionSpec { short: 'y', long: "unordered",         value: OptionValue::Flag,
+                 env: "DPG_UNORDERED",        values: Values::Any,
+                 help: "write passwords generated in parallel as soon\n\
+                        as they are ready (not in order)" },
+    OptionSpec { short: 'o', long: "format",            value: OptionValue::Required("format"),
+                 env: "DPG_FORMAT",           values: Values::OneOf(&["text", "plain", "json", "ndjson", "csv"]),
+                 help: "output format: text, plain (passwords only), [default: text]\n\
+                        json, ndjson or csv" },
+    OptionSpec { short: 'c', long: "clipboard",         value: OptionValue::Flag,
+                 env: "DPG_CLIPBOARD",        values: Values::Any,
+                 help: "copy password(s) to clipboard" },
+    OptionSpec { short: 'd', long: "dice",              value: OptionValue::Flag,
+                 env: "DPG_DICE",             values: Values::Any,
+                 help: "simulate throwing dices (slower)" },
+    OptionSpec { short: 'f', long: "profile",           value: OptionValue::Required("name"),
+                 env: "DPG_PROFILE",          values: Values::Any,
+                 help: "use settings of the profile defined in the config file" },
+    OptionSpec { short: 'i', long: "show-config",       value: OptionValue::Flag,
+                 env: "",                     values: Values::Any,
+                 help: "show effective settings and where they come from" },
+    OptionSpec { short: 'g', long: "ui-lang",           value: OptionValue::Required("language"),
+                 env: "",                     values: Values::OneOf(&["en", "pl"]),
+                 help: "language of messages (en or pl)       [default: from LANG]" },
+    OptionSpec { short: 'h', long: "help",              value: OptionValue::Flag,
+                 env: "",                     values: Values::Any,
+                 help: "this help" },
+];
 
-pub const MAX_OPTIONS_COUNT: usize = 6+1; // executable itself + 6 options
+/// Definition of a subcommand: its positional arguments and options it accepts.
+#[rustfmt::skip]
+pub struct CommandSpec {
+    pub name:       &'static str,
+    /// positional arguments (as shown in usage), empty if the command takes none
+    pub arguments:  &'static str,
+    pub about:      &'static str,
+    /// short names of options the command accepts (see `OPTION_SPECS`)
+    pub options:    &'static str,
+}
 
-const OPTION_PREFIXES: &'static str = "lwspchd";
+#[rustfmt::skip]
+pub static COMMAND_SPECS: [CommandSpec; 7] = [
+    CommandSpec { name: "gen",      arguments: "",                  options: "lwpesjuxmtrnbayocdfigh",
+                  about: "generate password(s) (default command)" },
+    CommandSpec { name: "lists",    arguments: "",                  options: "gh",
+                  about: "show available word lists" },
+    CommandSpec { name: "lookup",   arguments: "<rolls|word>...",   options: "lfgh",
+                  about: "find words for dice rolls (i.e. 11111) and dice rolls for words" },
+    CommandSpec { name: "check",    arguments: "[password]",        options: "gh",
+                  about: "estimate strength of a diceware password (read from standard input if not given)" },
+    CommandSpec { name: "roll",     arguments: "",                  options: "lwsfgh",
+                  about: "compose password from physical dice rolls (read from standard input)" },
+    CommandSpec { name: "completions", arguments: "<shell>",         options: "gh",
+                  about: "print shell completion script (bash, zsh or fish)" },
+    CommandSpec { name: "man",      arguments: "",                  options: "gh",
+                  about: "print manual page (roff)" },
+];
 
-#[cfg_attr(rustfmt, rustfmt_skip)]
-#[derive(Debug)]
+/// Where value of an option comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// option is not set anywhere, default value is used
+    Default,
+    /// defaults of the config file: path and line
+    Config(String, usize),
+    /// profile of the config file: name of the profile, path and line
+    Profile(String, String, usize),
+    /// environment variable
+    Environment(&'static str),
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::Default => f.write_str("default"),
+            Source::Config(ref path, line) => write!(f, "config file {}:{}", path, line),
+            Source::Profile(ref name, ref path, line) => write!(f, "profile '{}' {}:{}", name, path, line),
+            Source::Environment(variable) => write!(f, "environment variable {}", variable),
+            Source::CommandLine => f.write_str("command line"),
+        }
+    }
+}
+
+/// Subcommand to run along with its (validated) arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// generate passwords
+    Generate(Box<Options>),
+    /// show effective settings of `gen`: name of the option, its value and where the value comes from
+    ShowConfig(Vec<(&'static str, String, Source)>),
+    /// show available word lists
+    Lists,
+    /// look up words for dice rolls and dice rolls for words
+    Lookup { language: String, keys: Vec<String> },
+    /// estimate strength of a password (taken from standard input if not given)
+    Check { password: Option<String> },
+    /// compose password of `password_length` words from dice rolls taken from standard input
+    Roll { language: String, password_length: usize, separator: String },
+    /// print completion script for the shell
+    Completions { shell: Shell },
+    /// print manual page
+    Man,
+}
+
+#[rustfmt::skip]
+#[derive(Debug, Clone)]
 pub struct Options {
     /// two-letter language code specifying the word list to use for password(s) generation
     pub language:           String,
@@ -39,6 +225,32 @@ pub struct Options {
     pub clipboard:          bool,
     pub help:               bool,
     pub simulate_dices:     bool,
+    /// capitalization style applied to words
+    pub capitalization:     Capitalization,
+    /// insert extra special character or digit (Reinhold's extra security step)
+    pub extra_security:     ExtraSecurity,
+    /// password policy (i.e. imposed by a website) generated passwords have to comply with
+    pub policy:             Option<Policy>,
+    /// maximal length of password (in characters)
+    pub max_characters:     Option<usize>,
+    /// template describing structure of password (words, digits, symbols, literal text)
+    pub template:           Option<Template>,
+    /// characters to randomly choose separator (between each pair of words) from, `separator` is not used if specified
+    pub separator_set:      Option<Vec<char>>,
+    /// if specified, length of every password (in words) is chosen randomly from `password_length..=password_length_max`
+    pub password_length_max: Option<usize>,
+    /// if set, words do not repeat within a password (they are chosen without replacement)
+    pub unique_words:       bool,
+    /// if set, passwords do not repeat within a batch
+    pub unique_passwords:   bool,
+    /// format passwords are written in (by the command-line utility)
+    pub format:             OutputFormat,
+    /// if specified, passwords are generated in parallel by the given number of threads
+    pub threads:            Option<usize>,
+    /// if set, passwords generated in parallel are passed on as soon as they are ready, instead of the order they have been started in
+    pub unordered:          bool,
+    /// language of messages (of the command-line utility, and the strength report of `OutputFormat::Text`)
+    pub ui_language:        UiLanguage,
 }
 
 impl PartialEq for Options {
@@ -50,60 +262,481 @@ impl PartialEq for Options {
         && self.clipboard == other.clipboard
         && self.help == other.help
         && self.simulate_dices == other.simulate_dices
+        && self.capitalization == other.capitalization
+        && self.extra_security == other.extra_security
+        && self.policy == other.policy
+        && self.max_characters == other.max_characters
+        && self.template == other.template
+        && self.separator_set == other.separator_set
+        && self.password_length_max == other.password_length_max
+        && self.unique_words == other.unique_words
+        && self.unique_passwords == other.unique_passwords
+        && self.format == other.format
+        && self.threads == other.threads
+        && self.unordered == other.unordered
+        && self.ui_language == other.ui_language
+    }
+}
+
+/// Defaults of the command line (English list, a single password, words separated by dash, no transformations).
+/// Number of words has no default there (`-w` is required), so 6 words are used - as by `PasswordGenerator::builder()`.
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            language: s!("en"),
+            separator: DEFAULT_SEPARATOR.to_string(),
+            password_length: 6,
+            password_count: DEFAULT_PASSWORD_COUNT,
+            clipboard: false,
+            help: false,
+            simulate_dices: false,
+            capitalization: Capitalization::Lower,
+            extra_security: ExtraSecurity::Disabled,
+            policy: None,
+            max_characters: None,
+            template: None,
+            separator_set: None,
+            password_length_max: None,
+            unique_words: false,
+            unique_passwords: false,
+            format: OutputFormat::Text,
+            threads: None,
+            unordered: false,
+            ui_language: UiLanguage::En,
+        }
+    }
+}
+
+/// Parses Vec<String> as command-line arguments of a subcommand: `dpg <command> [options] [arguments]`.
+/// Arguments starting with an option (i.e. `dpg -w:5`) are the same as `dpg gen -w:5`.
+/// If help has been requested (`dpg help [command]`, `dpg [command] -h`), `DpgError::Help` is returned with name of the command.
+pub fn parse_command(args: Vec<String>) -> Result<Command, DpgError> {
+    parse_command_with_defaults(args, None, &HashMap::new())
+}
+
+/// Works as `parse_command`, but options not given on the command line are taken from environment variables
+/// (`DPG_LANG`, `DPG_WORDS`, etc. - see `OptionSpec::env`), or from the config: the profile selected with `--profile`
+/// first, then defaults. So command line takes precedence over environment, environment over the profile,
+/// and the profile over the defaults.
+pub fn parse_command_with_defaults(
+    args: Vec<String>,
+    config: Option<&Config>,
+    env: &HashMap<String, String>,
+) -> Result<Command, DpgError> {
+    if args.len() <= 1 {
+        return Err(DpgError::NoArguments);
+    }
+    let args = move_leading_ui_language(args);
+    if args[1].starts_with('-') {
+        let (opts, _arguments) = parse_arguments(&COMMAND_SPECS[0], &args[1..])?;
+        validate_arguments(&opts)?;
+        return create_generate_command(&apply_defaults(&COMMAND_SPECS[0], config, env, opts)?);
+    }
+    if args[1] == "help" {
+        return match args.get(2).filter(|arg| !arg.starts_with('-')) {
+            None => Err(DpgError::Help(None)),
+            Some(name) => Err(DpgError::Help(Some(find_command(name)?.name.to_string()))),
+        };
+    }
+    let command = find_command(&args[1])?;
+    let (opts, arguments) = parse_arguments(command, &args[2..])?;
+    if command.name != "gen" && opts.contains_key("h") {
+        return Err(DpgError::Help(Some(command.name.to_string())));
+    }
+    let result = match command.name {
+        "gen" => validate_arguments(&opts)
+            .and_then(|()| apply_defaults(command, config, env, opts))
+            .and_then(|settings| create_generate_command(&settings)),
+        _ => apply_defaults(command, config, env, opts)
+            .and_then(|settings| create_command(command, &values(&settings), arguments)),
+    };
+    // help of `gen` has been requested explicitly, so it is shown instead of the general one
+    result.map_err(|error| match error {
+        DpgError::Help(None) => DpgError::Help(Some(command.name.to_string())),
+        error => error,
+    })
+}
+
+/// Language of messages could be chosen before the command name as well (`dpg -g pl check`), as it applies to every command:
+/// such options are moved after the arguments of the command (but before `--`), so they are parsed as options of the command.
+fn move_leading_ui_language(mut args: Vec<String>) -> Vec<String> {
+    let mut end = 1;
+    while let Some(arg) = args.get(end) {
+        end += match ui_language_option(arg) {
+            Some(None) if args.get(end + 1).is_some_and(|next| is_option_value(next)) => 2,
+            Some(_) => 1,
+            None => break,
+        };
+    }
+    let is_command = |name: &String| name == "help" || COMMAND_SPECS.iter().any(|command| command.name == name);
+    if end == 1 || !args.get(end).is_some_and(is_command) {
+        return args;
+    }
+    let leading = args.drain(1..end).collect::<Vec<String>>();
+    let position = args.iter().position(|arg| arg == "--").unwrap_or(args.len());
+    args.splice(position..position, leading);
+    args
+}
+
+/// Recognizes the option choosing language of messages (`-g`/`--ui-lang`) the way `parse_arguments` does, so arguments could be
+/// scanned for it before they are parsed (see `UiLanguage::select`): `Some(Some(value))` if the value is attached to the option,
+/// `Some(None)` if it should be the next argument (see `is_option_value`), `None` for any other argument.
+pub(crate) fn ui_language_option(arg: &str) -> Option<Option<&str>> {
+    if let Some(long) = arg.strip_prefix("--") {
+        let (name, attached) = match long.find('=') {
+            Some(index) => (&long[..index], Some(&long[index + 1..])),
+            None => (long, None),
+        };
+        return Some(attached).filter(|_| name.eq_ignore_ascii_case("ui-lang"));
+    }
+    let short = arg.strip_prefix('-')?;
+    for (index, c) in short.char_indices() {
+        let spec = OPTION_SPECS.iter().find(|spec| spec.short == c.to_ascii_lowercase())?;
+        let rest = &short[index + c.len_utf8()..];
+        match spec.value {
+            // flags could be grouped with the option, i.e. `-cg:pl`
+            OptionValue::Flag if !rest.starts_with(':') => continue,
+            _ if spec.short == 'g' => {
+                return match rest.strip_prefix(':') {
+                    Some(value) => Some(Some(value)),
+                    None if rest.is_empty() => Some(None),
+                    None => Some(Some(rest)),
+                };
+            }
+            // the rest is the value of another option
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Whether the argument following an option requiring a value is taken as its value: anything but an option
+/// (a single dash is a valid value, i.e. a separator).
+pub(crate) fn is_option_value(next: &str) -> bool {
+    next == "-" || !next.starts_with('-')
+}
+
+fn create_command(command: &CommandSpec, opts: &HashMap<String, String>, arguments: Vec<String>) -> Result<Command, DpgError> {
+    match command.name {
+        "lists" => Ok(Command::Lists),
+        "lookup" => create_lookup_command(opts, arguments),
+        "check" => create_check_command(arguments),
+        "roll" => create_roll_command(opts),
+        "completions" => create_completions_command(arguments),
+        _ => Ok(Command::Man),
+    }
+}
+
+/// Options are validated even if only effective settings are going to be shown.
+fn create_generate_command(settings: &Settings) -> Result<Command, DpgError> {
+    let options = create_options(&values(settings))?;
+    if !settings.contains_key("i") {
+        return Ok(Command::Generate(Box::new(options)));
+    }
+    let shown = command_options(&COMMAND_SPECS[0])
+        .filter(|spec| !['h', 'i', 'g'].contains(&spec.short))
+        .map(|spec| match settings.get(&spec.short.to_string()) {
+            Some((value, source)) => (spec.long, display_value(spec, value), source.clone()),
+            None => (spec.long, s!(default_value(spec.short)), Source::Default),
+        })
+        .collect();
+
+    Ok(Command::ShowConfig(shown))
+}
+
+fn display_value(spec: &OptionSpec, value: &str) -> String {
+    match (spec.value, value) {
+        (OptionValue::Flag, _) | (OptionValue::Optional(_), "") => s!("true"),
+        (_, value) => format!("'{}'", value),
+    }
+}
+
+fn default_value(option: char) -> &'static str {
+    match option {
+        'l' => "'en'",
+        'p' => "'1'",
+        's' => "'-'",
+        'u' => "'lower'",
+        'o' => "'text'",
+        'n' | 'b' | 'a' | 'y' | 'c' | 'd' | 'x' => "false",
+        _ => "not set",
     }
 }
 
-/// Parses Vec<String> as command-line arguments.
-/// It may exit with error (in case insufficient number of arguments are passed or unknown option is specified).
+/// Parses Vec<String> as command-line arguments of password generation (`gen` subcommand, the command name is not expected).
+/// Returns an error if insufficient number of arguments are passed, unknown option is specified, or options are invalid.
+/// If help has been requested, `DpgError::Help` is returned (help text is available via `help_message`).
+///
+/// Options could be given in a couple of forms: `-w:5` (the original one), `-w 5`, `-w5`, `--words 5` and `--words=5`.
+/// Short flags could be combined (`-cd` is the same as `-c -d`). Values are taken as they are (case is preserved).
 ///
 /// If parsing succeeds - an dpg::option_parser::Options struct is returned
-pub fn parse_command_line(args: Vec<String>) -> Options {
+pub fn parse_command_line(args: Vec<String>) -> Result<Options, DpgError> {
+    if args.len() <= 1 {
+        return Err(DpgError::NoArguments);
+    }
+    let (opts, _arguments) = parse_arguments(&COMMAND_SPECS[0], &args[1..])?;
 
-    let mut opts: HashMap<String, String> = HashMap::with_capacity(MAX_OPTIONS_COUNT);
-    let arg_count = args.len();
+    validate_arguments(&opts)?;
+    create_options(&values(&apply_defaults(&COMMAND_SPECS[0], None, &HashMap::new(), opts)?))
+}
 
-    match arg_count {
-        1 => {
-            eprintln!("error: insufficient parameters. Type 'dpg -h' for help.");
-            exit(ERR_NO_ARGUMENTS);
-        },
-        2...MAX_OPTIONS_COUNT => {
-            for i in { 1..arg_count} {
-                let (k, v) = get_option_key_value(&args[i]);
-                opts.insert(k, v);
+/// Values of options (by short name) along with their sources.
+type Settings = HashMap<String, (String, Source)>;
+
+/// Options of a single source: short name, value (`None` if turned off) and the source.
+type Layer = Vec<(char, Option<String>, Source)>;
+
+fn values(settings: &Settings) -> HashMap<String, String> {
+    settings.iter().map(|(option, (value, _))| (option.clone(), value.clone())).collect()
+}
+
+/// Fills in options accepted by the command, but not given on the command line, using environment variables and the config.
+fn apply_defaults(
+    command: &CommandSpec,
+    config: Option<&Config>,
+    env: &HashMap<String, String>,
+    opts: HashMap<String, String>,
+) -> Result<Settings, DpgError> {
+    let environment = environment_layer(command, env)?;
+    let profile = opts.get("f")
+        .or_else(|| environment.iter().find(|e| e.0 == 'f').and_then(|e| e.1.as_ref()))
+        .cloned();
+    let mut settings: Settings = HashMap::new();
+    match (config, profile) {
+        (None, Some(profile)) => return Err(DpgError::Config(Message::new("error.config.no_config", &[&profile]))),
+        (None, None) => {}
+        (Some(config), profile) => {
+            let source = |setting: &Setting| Source::Config(config.path.clone(), setting.line);
+            apply_layer(&mut settings, config_layer(command, &config.defaults, source));
+            if let Some(profile) = profile {
+                let source = |setting: &Setting| Source::Profile(profile.clone(), config.path.clone(), setting.line);
+                apply_layer(&mut settings, config_layer(command, config.profile(&profile)?, source));
             }
         }
-        _ => too_many_arguments_error(),
     }
+    apply_layer(&mut settings, environment);
+    apply_layer(&mut settings, opts.into_iter()
+        .map(|(option, value)| (option.chars().next().unwrap_or('-'), Some(value), Source::CommandLine))
+        .collect());
 
-    validate_arguments(&opts);
-    create_options(&opts)
+    Ok(settings)
 }
 
-fn validate_arguments(opts: &HashMap<String, String>) {
-    #[cfg(debug_assertions)]
-    println!("validating arguments...");
+fn config_layer<F: Fn(&Setting) -> Source>(command: &CommandSpec, settings: &[Setting], source: F) -> Layer {
+    settings.iter()
+        .filter(|setting| command.options.contains(setting.option))
+        .map(|setting| (setting.option, setting.value.clone(), source(setting)))
+        .collect()
+}
 
-    if (opts.len() == 1) && (opts.contains_key("h")) {
-        info();
-        exit(0);
+/// Options of the command taken from environment variables. Flags are turned on with `1`, `true`, `yes` or `on`,
+/// and off with `0`, `false`, `no`, `off` or an empty value. Values are checked the same way as values given on the command line.
+fn environment_layer(command: &CommandSpec, env: &HashMap<String, String>) -> Result<Layer, DpgError> {
+    let mut layer: Layer = Vec::new();
+    for spec in command_options(command).filter(|spec| !spec.env.is_empty()) {
+        let value = match env.get(spec.env) {
+            None => continue,
+            Some(value) => value,
+        };
+        let switch = match value.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Some(true),
+            "0" | "false" | "no" | "off" | "" => Some(false),
+            _ => None,
+        };
+        let value = match (spec.value, switch) {
+            (OptionValue::Flag, Some(enabled)) | (OptionValue::Optional(_), Some(enabled)) => {
+                if enabled { Some(String::new()) } else { None }
+            }
+            (OptionValue::Flag, None) => return Err(DpgError::ArgumentParsing(
+                Message::new("error.env_flag", &[&spec.env, value])
+            )),
+            _ => {
+                check_value(spec.short, value)
+                    .map_err(|message| DpgError::ArgumentParsing(Message::wrap("error.env_value", &[&spec.env], message)))?;
+                Some(value.to_string())
+            }
+        };
+        layer.push((spec.short, value, Source::Environment(spec.env)));
     }
+    Ok(layer)
+}
 
-    for k in opts.keys() {
-        if !OPTION_PREFIXES.contains(k) {
-            eprintln!("error: unknown option: -'{}'", k);
-            exit(ERR_UNKNOWN_OPTION);
+/// Applies options of a layer on top of the options set so far. Options that could not be combined with the ones
+/// of the layer (i.e. number of words when template is given) are dropped, so every layer could override the previous ones.
+fn apply_layer(settings: &mut Settings, layer: Layer) {
+    for &(option, _, _) in &layer {
+        let overridden = match option {
+            'w' | 'e' => "wet",
+            't' => "wetsxrmj",
+            's' | 'j' | 'x' | 'r' | 'm' => "t",
+            _ => "",
+        };
+        for overridden in overridden.chars() {
+            settings.remove(&overridden.to_string());
         }
     }
+    for (option, value, source) in layer {
+        match value {
+            Some(value) => settings.insert(option.to_string(), (value, source)),
+            None => settings.remove(&option.to_string()),
+        };
+    }
 }
 
-fn create_options(opts: &HashMap<String, String>) -> Options {
-    let options = Options {
-        language: opts.get("l").unwrap_or(&"en".to_string()).to_string(),
-        password_length: opts.get("w")
-            .expect("error parsing options: '-w' is required!")
-            .parse::<usize>()
+fn find_command(name: &str) -> Result<&'static CommandSpec, DpgError> {
+    COMMAND_SPECS.iter()
+        .find(|command| command.name == name)
+        .ok_or_else(|| DpgError::ArgumentParsing(Message::new("error.unknown_command", &[
+            &name,
+            &COMMAND_SPECS.iter().map(|command| command.name).collect::<Vec<&str>>().join(", "),
+        ])))
+}
+
+/// Parses arguments into map of (short) option names and their values (empty for flags), and positional arguments.
+/// Everything following `--` is taken as positional arguments.
+fn parse_arguments(command: &CommandSpec, args: &[String]) -> Result<(HashMap<String, String>, Vec<String>), DpgError> {
+    let mut opts: HashMap<String, String> = HashMap::new();
+    let mut arguments: Vec<String> = Vec::new();
+    let mut only_arguments = false;
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        if only_arguments || !arg.starts_with('-') || arg == "-" {
+            if command.arguments.is_empty() {
+                return Err(DpgError::ArgumentParsing(Message::new("error.unexpected_argument", &[arg])));
+            }
+            arguments.push(arg.to_string());
+            continue;
+        }
+        if arg == "--" {
+            only_arguments = true;
+            continue;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, attached) = match long.find('=') {
+                Some(index) => (&long[..index], Some(&long[index + 1..])),
+                None => (long, None),
+            };
+            let spec = find_long_option(command, name)
+                .ok_or_else(|| DpgError::UnknownOption(format!("--{}", name), suggest_option(command, name)))?;
+            let value = option_value(spec, &format!("--{}", spec.long), attached, &mut args)?;
+            opts.insert(spec.short.to_string(), value);
+            continue;
+        }
+        let short = &arg[1..];
+        for (index, c) in short.char_indices() {
+            let spec = find_short_option(command, c)
+                .ok_or_else(|| DpgError::UnknownOption(format!("-{}", c), None))?;
+            let rest = &short[index + c.len_utf8()..];
+            let name = format!("-{}", spec.short);
+            match spec.value {
+                // the original syntax allowed flags to be followed by a colon, i.e. `-c:`
+                OptionValue::Flag if rest == ":" => {
+                    opts.insert(spec.short.to_string(), String::new());
+                    break;
+                }
+                OptionValue::Flag if rest.starts_with(':') => {
+                    option_value(spec, &name, Some(&rest[1..]), &mut args)?;
+                }
+                OptionValue::Flag => {
+                    opts.insert(spec.short.to_string(), String::new());
+                    continue;
+                }
+                _ => {
+                    let attached = match rest.strip_prefix(':') {
+                        Some(value) => Some(value),
+                        None if rest.is_empty() => None,
+                        None => Some(rest),
+                    };
+                    let value = option_value(spec, &name, attached, &mut args)?;
+                    opts.insert(spec.short.to_string(), value);
+                    break;
+                }
+            }
+        }
+    }
+    Ok((opts, arguments))
+}
+
+/// Value of the option: either attached to it, or (if required) taken from the next argument.
+fn option_value<'a, I: Iterator<Item = &'a String>>(
+    spec: &OptionSpec,
+    name: &str,
+    attached: Option<&str>,
+    args: &mut ::std::iter::Peekable<I>,
+) -> Result<String, DpgError> {
+    match (spec.value, attached) {
+        (OptionValue::Flag, None) => Ok(String::new()),
+        (OptionValue::Flag, Some(_)) => Err(DpgError::ArgumentParsing(Message::new("error.flag_value", &[&name]))),
+        (_, Some(value)) => Ok(value.to_string()),
+        (OptionValue::Optional(_), None) => Ok(String::new()),
+        (OptionValue::Required(value_name), None) => {
+            match args.peek().is_some_and(|next| is_option_value(next)) {
+                true => Ok(args.next().cloned().unwrap_or_default()),
+                false => Err(DpgError::ArgumentParsing(Message::new("error.missing_value", &[&name, &value_name]))),
+            }
+        }
+    }
+}
+
+/// Options accepted by the command.
+pub(crate) fn command_options(command: &CommandSpec) -> impl Iterator<Item = &'static OptionSpec> + '_ {
+    OPTION_SPECS.iter().filter(move |spec| command.options.contains(spec.short))
+}
+
+fn find_short_option(command: &CommandSpec, short: char) -> Option<&'static OptionSpec> {
+    command_options(command).find(|spec| spec.short == short.to_ascii_lowercase())
+}
+
+fn find_long_option(command: &CommandSpec, long: &str) -> Option<&'static OptionSpec> {
+    command_options(command).find(|spec| spec.long.eq_ignore_ascii_case(long))
+}
+
+/// The closest (long) option to the unknown one, if it is close enough to be a typo.
+fn suggest_option(command: &CommandSpec, unknown: &str) -> Option<String> {
+    let unknown = unknown.to_lowercase();
+    command_options(command)
+        .map(|spec| (edit_distance(&unknown, spec.long), spec.long))
+        .filter(|&(distance, long)| distance <= 2.max(long.len() / 3))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, long)| format!("--{}", long))
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + (ca != *cb) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn validate_arguments(opts: &HashMap<String, String>) -> Result<(), DpgError> {
+    if (opts.len() == 1) && (opts.contains_key("h")) {
+        return Err(DpgError::Help(None));
+    }
+    Ok(())
+}
+
+fn create_options(opts: &HashMap<String, String>) -> Result<Options, DpgError> {
+    let password_length = match opts.contains_key("e") || opts.contains_key("t") {
+        true => 0,
+        false => opts.get("w")
+            .ok_or_else(|| DpgError::ArgumentParsing(Message::new("error.words_required", &[])))?
+            .split('-')
+            .next()
+            .and_then(|min| min.parse::<usize>().ok())
             .unwrap_or(0),
+    };
+    let mut options = Options {
+        language: opts.get("l").unwrap_or(&"en".to_string()).to_lowercase(),
+        password_length,
         clipboard: opts.contains_key("c"),
         password_count: opts.get("p")
             .unwrap_or(&"1".to_string())
@@ -113,94 +746,346 @@ fn create_options(opts: &HashMap<String, String>) -> Options {
             .unwrap_or(&DEFAULT_SEPARATOR.to_string())
             .to_string(),
         simulate_dices: opts.contains_key("d"),
+        capitalization: get_capitalization(opts)?,
+        extra_security: get_extra_security(opts)?,
+        policy: get_policy(opts)?,
+        max_characters: get_max_characters(opts)?,
+        template: get_template(opts)?,
+        separator_set: get_separator_set(opts)?,
+        password_length_max: get_password_length_max(opts),
+        unique_words: opts.contains_key("n"),
+        unique_passwords: opts.contains_key("b"),
+        threads: get_threads(opts)?,
+        unordered: opts.contains_key("y"),
+        format: get_format(opts)?,
+        ui_language: get_ui_language(opts)?,
         help: opts.contains_key("h"),
     };
-    validate_options(&options);
+    if let Some(ref template) = options.template {
+        options.password_length = template.word_count();
+    }
+    if let Some(bits) = opts.get("e") {
+        options.password_length = password_length_for_entropy(&options, bits, opts.contains_key("w"))?;
+    }
+    validate_options(&options)?;
 
-    options
+    Ok(options)
 }
 
-fn validate_options(options: &Options) {
-    let language = options.language.as_str();
-    let password_length = options.password_length;
-    let password_count = options.password_count;
-    #[cfg(debug_assertions)]
-    println!(
-        "[passed parameter to check] language: {} password: {}",
-        language, password_length
-    );
-    if password_length < MIN_WORDS_COUNT || password_length > MAX_WORDS_COUNT {
-        eprintln!(
-            "error: password should consist of at least {} and max {} words",
-            MIN_WORDS_COUNT, MAX_WORDS_COUNT
-        );
-        exit(ERR_ARGUMENT_PARSING);
+fn get_capitalization(opts: &HashMap<String, String>) -> Result<Capitalization, DpgError> {
+    let style = opts.get("u").map(|s| s.as_str()).unwrap_or("lower");
+    style.parse::<Capitalization>()
+        .map_err(|message| DpgError::ArgumentParsing(Message::wrap("error.supported_cases", &[], message)))
+}
+
+fn get_format(opts: &HashMap<String, String>) -> Result<OutputFormat, DpgError> {
+    let format = opts.get("o").map(|s| s.as_str()).unwrap_or("text");
+    format.parse::<OutputFormat>()
+        .map_err(|message| DpgError::ArgumentParsing(Message::wrap("error.supported_formats", &[], message)))
+}
+
+fn get_ui_language(opts: &HashMap<String, String>) -> Result<UiLanguage, DpgError> {
+    match opts.get("g") {
+        None => Ok(UiLanguage::En),
+        Some(language) => language.parse::<UiLanguage>().map_err(DpgError::ArgumentParsing),
     }
-    if password_count < MIN_PASSWORD_COUNT || password_count > MAX_PASSWORD_COUNT {
-        eprintln!("error: it is possible to generate {}-{} passwords at once",
-            MIN_PASSWORD_COUNT, MAX_PASSWORD_COUNT);
-        exit(ERR_ARGUMENT_PARSING);
+}
+
+fn get_extra_security(opts: &HashMap<String, String>) -> Result<ExtraSecurity, DpgError> {
+    match opts.get("x") {
+        None => Ok(ExtraSecurity::Disabled),
+        Some(rolls) if rolls.is_empty() => Ok(ExtraSecurity::SimulatedDice),
+        Some(rolls) => ::extra_security::parse_dice_rolls(rolls)
+            .map(ExtraSecurity::PhysicalDice)
+            .map_err(DpgError::ArgumentParsing),
     }
-    if language != "en" && language != "pl" {
-        eprintln!("error: language: '{}' is not supported!", language);
-        exit(ERR_ARGUMENT_PARSING);
+}
+
+/// Number of threads generating passwords, if the value is not given - one thread for every CPU.
+fn get_threads(opts: &HashMap<String, String>) -> Result<Option<usize>, DpgError> {
+    match opts.get("a") {
+        None => Ok(None),
+        Some(threads) if threads.is_empty() => Ok(Some(::parallel::available_threads())),
+        Some(threads) => match threads.parse::<usize>() {
+            Ok(threads) if threads > 0 => Ok(Some(threads)),
+            _ => Err(DpgError::ArgumentParsing(Message::new("error.threads", &[]))),
+        },
     }
-    if options.help {
-        info();
-        exit(0);
+}
+
+fn get_policy(opts: &HashMap<String, String>) -> Result<Option<Policy>, DpgError> {
+    match opts.get("r") {
+        None => Ok(None),
+        Some(rules) => rules.parse::<Policy>()
+            .map(Some)
+            .map_err(DpgError::ArgumentParsing),
+    }
+}
+
+fn get_max_characters(opts: &HashMap<String, String>) -> Result<Option<usize>, DpgError> {
+    let max_characters = opts.get("m").map(|max| max.parse::<usize>().unwrap_or(0));
+    if max_characters == Some(0) {
+        return Err(DpgError::ArgumentParsing(Message::new("error.max_characters", &[])));
     }
+
+    Ok(max_characters)
 }
 
-fn get_option_key_value(option: &str) -> (String, String) {
-    check_argument_format(option);
+fn get_password_length_max(opts: &HashMap<String, String>) -> Option<usize> {
+    let words = opts.get("w")?;
+    let index = words.find('-')?;
+
+    Some(words[index + 1..].parse::<usize>().unwrap_or(0))
+}
 
-    let input = match option.starts_with("-") {
-        true => option[1..].to_ascii_lowercase(),
-        false => option[..].to_ascii_lowercase(),
+fn get_separator_set(opts: &HashMap<String, String>) -> Result<Option<Vec<char>>, DpgError> {
+    let set = match opts.get("j") {
+        None => return Ok(None),
+        Some(set) if set.eq_ignore_ascii_case("digits") => s!("0123456789"),
+        Some(set) => set.to_string(),
     };
+    let mut separator_set: Vec<char> = Vec::new();
+    for c in set.chars() {
+        if !separator_set.contains(&c) {
+            separator_set.push(c);
+        }
+    }
+    if separator_set.is_empty() {
+        return Err(DpgError::ArgumentParsing(Message::new("error.empty_separator_set", &[])));
+    }
 
-    let index = input.find(":").unwrap_or(input.len());
-    let (k, v) = input.split_at(index);
-    #[cfg(debug_assertions)]
-    println!("k/v: {:?}", (k, v));
+    Ok(Some(separator_set))
+}
+
+fn get_template(opts: &HashMap<String, String>) -> Result<Option<Template>, DpgError> {
+    let template = match opts.get("t") {
+        None => return Ok(None),
+        Some(template) => template,
+    };
+    if let Some(conflicting) = "wesxrmj".chars().find(|k| opts.contains_key(&k.to_string())) {
+        return Err(DpgError::ArgumentParsing(Message::new("error.template_conflict", &[&conflicting])));
+    }
+    template.parse::<Template>()
+        .map(Some)
+        .map_err(DpgError::ArgumentParsing)
+}
+
+fn password_length_for_entropy(options: &Options, bits: &str, words_specified: bool) -> Result<usize, DpgError> {
+    if words_specified {
+        return Err(DpgError::ArgumentParsing(Message::new("error.words_and_entropy", &[])));
+    }
+    let bits = bits.parse::<f64>().unwrap_or(0.0);
+    if bits <= 0.0 {
+        return Err(DpgError::ArgumentParsing(Message::new("error.entropy", &[])));
+    }
+    let repository = ::diceware_info::build_diceware_repository();
+    ::passwords::password_length_for_entropy(options, &repository, bits)
+        .ok_or_else(|| DpgError::ArgumentParsing(Message::new("error.entropy_unreachable", &[&bits, &MAX_WORDS_COUNT])))
+}
+
+fn get_language(opts: &HashMap<String, String>) -> Result<String, DpgError> {
+    let language = opts.get("l").map(|l| l.to_lowercase()).unwrap_or_else(|| s!("en"));
+    match language.as_str() {
+        "en" | "pl" => Ok(language),
+        _ => Err(DpgError::ArgumentParsing(Message::new("error.unsupported_language", &[&language]))),
+    }
+}
+
+fn create_lookup_command(opts: &HashMap<String, String>, keys: Vec<String>) -> Result<Command, DpgError> {
+    if keys.is_empty() {
+        return Err(DpgError::ArgumentParsing(Message::new("error.nothing_to_look_up", &[])));
+    }
+    Ok(Command::Lookup { language: get_language(opts)?, keys })
+}
 
-    (k.to_string(), v.replace(":", ""))
+fn create_check_command(arguments: Vec<String>) -> Result<Command, DpgError> {
+    if arguments.len() > 1 {
+        return Err(DpgError::ArgumentParsing(Message::new("error.one_password", &[])));
+    }
+    Ok(Command::Check { password: arguments.into_iter().next() })
 }
 
+fn create_completions_command(arguments: Vec<String>) -> Result<Command, DpgError> {
+    match arguments.len() {
+        1 => Ok(Command::Completions { shell: arguments[0].parse::<Shell>().map_err(DpgError::ArgumentParsing)? }),
+        _ => Err(DpgError::ArgumentParsing(Message::new("error.one_shell", &[]))),
+    }
+}
 
-fn too_many_arguments_error() {
-    let info_message = " \
-error: too many options provided! \r\
-\r\
-use -h for help";
-    println!("{:}", info_message);
-    exit(ERR_TOO_MANY_OPTIONS);
+fn create_roll_command(opts: &HashMap<String, String>) -> Result<Command, DpgError> {
+    let password_length = opts.get("w")
+        .ok_or_else(|| DpgError::ArgumentParsing(Message::new("error.roll_words_required", &[])))?
+        .parse::<usize>()
+        .unwrap_or(0);
+    if password_length < MIN_WORDS_COUNT || password_length > MAX_WORDS_COUNT {
+        return Err(DpgError::ArgumentParsing(Message::new("error.word_count", &[&MIN_WORDS_COUNT, &MAX_WORDS_COUNT])));
+    }
+    Ok(Command::Roll {
+        language: get_language(opts)?,
+        password_length,
+        separator: opts.get("s").cloned().unwrap_or_else(|| DEFAULT_SEPARATOR.to_string()),
+    })
 }
 
-fn info() {
-    let info_message = "dpg - diceware password generator \r
-author: Maciek Talaska <maciek.talaska@gmail.com> \r
-source: github.com/MaciekTalaska/dpg \r
-\r
-options:
--l:<language>   language (en or pl)                   [default: en]\r
--w:<number>     password length (in words)            [range: 1-255]\r
--p:<number>     number of passwords to generate       [range: 1-255, default: 1]\r
--s:<char>       character to separate words with      [default: '-']\r
--c              copy password(s) to clipboard\r\
--d              simulate throwing dices (slower)\r\
-\r
--h              this help\r
-\n";
-    print!("{}", info_message);
+/// Checks value of a single option (i.e. set in the config file), independently of the other options.
+pub fn check_value(option: char, value: &str) -> Result<(), Message> {
+    let mut opts: HashMap<String, String> = HashMap::new();
+    opts.insert(option.to_string(), value.to_string());
+    let result = match option {
+        'l' => get_language(&opts).map(|_| ()),
+        'w' => {
+            let (min, max) = match value.find('-') {
+                Some(index) => (&value[..index], Some(&value[index + 1..])),
+                None => (value, None),
+            };
+            let min = min.parse::<usize>().unwrap_or(0);
+            let max = max.map(|max| max.parse::<usize>().unwrap_or(0)).unwrap_or(min);
+            match min >= MIN_WORDS_COUNT && min <= max && max <= MAX_WORDS_COUNT {
+                true => Ok(()),
+                false => Err(DpgError::ArgumentParsing(
+                    Message::new("error.word_count_range", &[&MIN_WORDS_COUNT, &MAX_WORDS_COUNT])
+                )),
+            }
+        }
+        'p' => match value.parse::<usize>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(DpgError::ArgumentParsing(Message::new("error.password_count", &[]))),
+        },
+        'e' => match value.parse::<f64>() {
+            Ok(bits) if bits > 0.0 => Ok(()),
+            _ => Err(DpgError::ArgumentParsing(Message::new("error.entropy", &[]))),
+        },
+        'j' => get_separator_set(&opts).map(|_| ()),
+        'u' => get_capitalization(&opts).map(|_| ()),
+        'o' => get_format(&opts).map(|_| ()),
+        'g' => get_ui_language(&opts).map(|_| ()),
+        'x' => get_extra_security(&opts).map(|_| ()),
+        'a' => get_threads(&opts).map(|_| ()),
+        'm' => get_max_characters(&opts).map(|_| ()),
+        't' => value.parse::<Template>().map(|_| ()).map_err(DpgError::ArgumentParsing),
+        'r' => get_policy(&opts).map(|_| ()),
+        _ => Ok(()),
+    };
+    result.map_err(|error| match error {
+        DpgError::ArgumentParsing(message) => message,
+        error => unreachable!("checking a value could not fail with: {:?}", error),
+    })
 }
 
-fn check_argument_format(option: &str) {
-    if !option.starts_with("-") {
-        eprintln!("unrecognized option: {}", option);
-        eprintln!("  are you missing a '-' prefix?");
-        exit(ERR_ARGUMENT_PARSING);
+fn validate_options(options: &Options) -> Result<(), DpgError> {
+    check_options(options).map_err(DpgError::ArgumentParsing)?;
+    if options.help {
+        return Err(DpgError::Help(None));
     }
+    Ok(())
+}
+
+/// Checks if passwords could be generated using specified options, returns description of the problem if not.
+pub fn check_options(options: &Options) -> Result<(), Message> {
+    let language = options.language.as_str();
+    let password_length = options.password_length;
+    let password_count = options.password_count;
+    if password_length < MIN_WORDS_COUNT || password_length > MAX_WORDS_COUNT {
+        return Err(Message::new("error.word_count", &[&MIN_WORDS_COUNT, &MAX_WORDS_COUNT]));
+    }
+    if let Some(password_length_max) = options.password_length_max {
+        if password_length_max < password_length || password_length_max > MAX_WORDS_COUNT {
+            return Err(Message::new("error.word_range", &[&MIN_WORDS_COUNT, &MAX_WORDS_COUNT]));
+        }
+    }
+    if password_count == UNLIMITED_PASSWORD_COUNT {
+        if options.clipboard {
+            return Err(Message::new("error.unlimited_clipboard", &[]));
+        }
+        if options.unique_passwords {
+            return Err(Message::new("error.unlimited_unique", &[]));
+        }
+    }
+    if let ExtraSecurity::PhysicalDice(ref rolls) = options.extra_security {
+        if password_count != 1 {
+            return Err(Message::new("error.dice_rolls_one_password", &[]));
+        }
+        ::extra_security::check_dice_rolls(password_length, rolls)?;
+    }
+    if language != "en" && language != "pl" {
+        return Err(Message::new("error.unsupported_language", &[&language]));
+    }
+    if options.policy.is_some() || options.max_characters.is_some() {
+        let repository = ::diceware_info::build_diceware_repository();
+        ::passwords::validate_policy(options, &repository)
+            .map_err(|message| Message::wrap("error.policy_not_met", &[], message))?;
+    }
+    if options.unique_words || options.unique_passwords {
+        let repository = ::diceware_info::build_diceware_repository();
+        ::passwords::validate_uniqueness(options, &repository)?;
+    }
+    Ok(())
+}
+
+/// Usage information printed when help is requested: general one (listing all the commands), or help of the given command.
+/// Both are generated from definitions of the commands and options.
+pub fn help_message(command: Option<&str>) -> String {
+    help_message_in(command, UiLanguage::En)
+}
+
+/// Works as `help_message`, but help is written in the given language.
+pub fn help_message_in(command: Option<&str>, language: UiLanguage) -> String {
+    let header = language.text("help.header");
+    match command.and_then(|name| COMMAND_SPECS.iter().find(|command| command.name == name)) {
+        None => {
+            let mut help = format!("{}{}", header, language.text("help.usage"));
+            for command in COMMAND_SPECS.iter() {
+                help.push_str(&format!("  {:<13}{}\n", command.name, language.command_about(command)));
+            }
+            help.push_str(language.text("help.gen_options"));
+            help.push_str(&options_help(&COMMAND_SPECS[0], language));
+            help.push_str(language.text("help.values"));
+            help.push_str(language.text("help.other_commands"));
+            help
+        }
+        Some(command) => {
+            let arguments = match command.arguments {
+                "" => String::new(),
+                arguments => format!(" {}", arguments),
+            };
+            let footer = match command_options(command).find(|spec| spec.value != OptionValue::Flag) {
+                None => String::new(),
+                Some(spec) => {
+                    let value = match spec.value {
+                        OptionValue::Required(value) | OptionValue::Optional(value) => value,
+                        OptionValue::Flag => "",
+                    };
+                    language.format("help.command_values", &[&spec.short, &value, &spec.long])
+                }
+            };
+            format!(
+                "{}{}{}{}",
+                header,
+                language.format("help.command", &[&command.name, &language.command_about(command), &arguments]),
+                options_help(command, language),
+                footer
+            )
+        }
+    }
+}
+
+fn options_help(command: &CommandSpec, language: UiLanguage) -> String {
+    let mut help = String::new();
+    for spec in command_options(command) {
+        let usage = match spec.value {
+            OptionValue::Flag => format!("-{}, --{}", spec.short, spec.long),
+            OptionValue::Required(value) => format!("-{}, --{} <{}>", spec.short, spec.long, value),
+            OptionValue::Optional(value) => format!("-{}, --{}[=<{}>]", spec.short, spec.long, value),
+        };
+        if spec.short == 'h' && command.options.len() > 1 {
+            help.push('\n');
+        }
+        for (index, line) in language.option_help(spec).lines().enumerate() {
+            let usage = if index == 0 { usage.as_str() } else { "" };
+            help.push_str(&format!("{:<29}{}\n", usage, line));
+        }
+    }
+    help
 }
 
 #[cfg(test)]
@@ -208,45 +1093,54 @@ mod option_parser_tests {
     use super::*;
 
     #[test]
-    #[should_panic(expected = "1")]
     fn should_not_work_with_empty_argument_list() {
         let args = vec![s!("./dpg")];
-        let _options = parse_command_line(args);
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 1);
     }
 
     #[test]
-    #[should_panic(expected = "2")]
-    fn minimum_1_password_should_be_generated() {
+    fn zero_passwords_mean_unlimited_stream() {
         let args = vec![s!("./dpg"), s!("-p:0"), s!("-w:5")];
-        let _options = parse_command_line(args);
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.password_count, UNLIMITED_PASSWORD_COUNT);
     }
 
     #[test]
-    #[should_panic(expected = "2")]
-    fn max_255_could_be_generated() {
-        let args = vec![s!("./dpg"), s!("-p:256"), s!("-w:5")];
-        let _options = parse_command_line(args);
+    fn number_of_passwords_is_not_limited() {
+        let args = vec![s!("./dpg"), s!("-p:1000000"), s!("-w:5")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.password_count, 1000000);
+    }
+
+    #[test]
+    fn unlimited_stream_could_not_be_copied_or_kept_unique() {
+        for flag in &["-c", "-b"] {
+            let args = vec![s!("./dpg"), s!("-p:0"), s!("-w:5"), flag.to_string()];
+            let error = parse_command_line(args).unwrap_err();
+            assert_eq!(error.exit_code(), 2);
+        }
     }
 
     #[test]
-    #[should_panic(expected = "2")]
     fn password_must_be_at_least_1_word_long() {
         let args = vec![s!("./dpg"), s!("-w:0")];
-        let _options = parse_command_line(args);
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
     }
 
     #[test]
-    #[should_panic(expected = "0")]
     fn help_is_available() {
         let args = vec![s!("./dpg"), s!("-h")];
-        let _options = parse_command_line(args);
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 0);
     }
 
     #[test]
-    #[should_panic(expected = "2")]
     fn password_could_be_at_most_255_words_long() {
         let args = vec![s!("./dpg"), s!("-w:256")];
-        let _options = parse_command_line(args);
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
     }
 
 
@@ -254,16 +1148,8 @@ mod option_parser_tests {
     fn only_words_is_required_parameter() {
         let args = vec![s!("./dpg"),
                         s!("-w:5")];
-        let options = parse_command_line(args);
-        let expected_options = Options {
-            language: "en".to_string(),
-            password_length: 5,
-            password_count: 1,
-            separator: "-".to_string(),
-            simulate_dices: false,
-            help: false,
-            clipboard: false,
-        };
+        let options = parse_command_line(args).unwrap();
+        let expected_options = Options { password_length: 5, ..Default::default() };
         assert_eq!(options, expected_options);
     }
 
@@ -272,16 +1158,8 @@ mod option_parser_tests {
         let args = vec![s!("./dpg"),
                         s!("-l:pl"),
                         s!("-w:6")];
-        let options = parse_command_line(args);
-        let expected_options = Options {
-            language: "pl".to_string(),
-            password_length: 6,
-            password_count: 1,
-            separator: "-".to_string(),
-            simulate_dices: false,
-            help: false,
-            clipboard: false,
-        };
+        let options = parse_command_line(args).unwrap();
+        let expected_options = Options { language: "pl".to_string(), password_length: 6, ..Default::default() };
         assert_eq!(options, expected_options);
     }
 
@@ -291,15 +1169,12 @@ mod option_parser_tests {
                         s!("-l:pl"),
                         s!("-w:3"),
                         s!("-p:5")];
-        let options = parse_command_line(args);
+        let options = parse_command_line(args).unwrap();
         let expected_options = Options {
             language: "pl".to_string(),
             password_length: 3,
             password_count: 5,
-            separator: "-".to_string(),
-            simulate_dices: false,
-            help: false,
-            clipboard: false,
+            ..Default::default()
         };
         assert_eq!(options, expected_options);
     }
@@ -312,15 +1187,13 @@ mod option_parser_tests {
                         s!("-w:7"),
                         s!("-p:4"),
                         s!("-s:.")];
-        let options = parse_command_line(args);
+        let options = parse_command_line(args).unwrap();
         let expected_options = Options {
             language: "pl".to_string(),
             password_length: 7,
             password_count: 4,
             separator: ".".to_string(),
-            simulate_dices: false,
-            help: false,
-            clipboard: false,
+            ..Default::default()
         };
         assert_eq!(options, expected_options);
     }
@@ -333,15 +1206,14 @@ mod option_parser_tests {
                         s!("-p:5"),
                         s!("-s:."),
                         s!("-c")];
-        let options = parse_command_line(args);
+        let options = parse_command_line(args).unwrap();
         let expected_options = Options {
             language: "pl".to_string(),
             password_length: 8,
             password_count: 5,
             separator: ".".to_string(),
-            simulate_dices: false,
-            help: false,
             clipboard: true,
+            ..Default::default()
         };
         assert_eq!(options, expected_options);
     }
@@ -355,21 +1227,20 @@ mod option_parser_tests {
                         s!("-s:."),
                         s!("-c"),
                         s!("-d")];
-        let options = parse_command_line(args);
+        let options = parse_command_line(args).unwrap();
         let expected_options = Options {
             language: "pl".to_string(),
             password_length: 9,
             password_count: 6,
             separator: ".".to_string(),
             simulate_dices: true,
-            help: false,
             clipboard: true,
+            ..Default::default()
         };
         assert_eq!(options, expected_options);
     }
 
     #[test]
-    #[should_panic(expected = "0")]
     fn language_password_length_count_separator_clipboard_simulate_dices_help() {
         let args = vec![s!("./dpg"),
                         s!("-l:pl"),
@@ -377,40 +1248,585 @@ mod option_parser_tests {
                         s!("-p:6"),
                         s!("-s:."),
                         s!("-h")];
-        let options = parse_command_line(args);
-        let expected_options = Options {
-            language: "pl".to_string(),
-            password_length: 9,
-            password_count: 6,
-            separator: ".".to_string(),
-            simulate_dices: true,
-            help: true,
-            clipboard: true,
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error, DpgError::Help(None));
+    }
+
+    #[test]
+    fn password_length_is_calculated_from_target_entropy() {
+        let args = vec![s!("./dpg"), s!("-e:77")];
+        let options = parse_command_line(args).unwrap();
+        // English list contains 1296 words: 7 words give ~72.4 bits, 8 words ~82.7 bits
+        assert_eq!(options.password_length, 8);
+    }
+
+    #[test]
+    fn target_entropy_takes_selected_list_into_account() {
+        let args = vec![s!("./dpg"), s!("-l:pl"), s!("-e:77")];
+        let options = parse_command_line(args).unwrap();
+        // Polish list contains 3888 words: 6 words give ~71.6 bits, 7 words ~83.5 bits
+        assert_eq!(options.password_length, 7);
+    }
+
+    #[test]
+    fn target_entropy_unreachable_with_255_words_should_fail() {
+        let args = vec![s!("./dpg"), s!("-e:5000")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn words_and_target_entropy_can_not_be_used_together() {
+        let args = vec![s!("./dpg"), s!("-w:5"), s!("-e:60")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn capitalization_style_could_be_specified() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-u:title")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.capitalization, Capitalization::Title);
+    }
+
+    #[test]
+    fn unknown_capitalization_style_should_fail() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-u:snake")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn extra_character_could_be_inserted_using_simulated_dice() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-x")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.extra_security, ExtraSecurity::SimulatedDice);
+    }
+
+    #[test]
+    fn extra_character_could_be_inserted_using_physical_dice() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-x:3425")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.extra_security, ExtraSecurity::PhysicalDice(vec![3, 4, 2, 5]));
+    }
+
+    #[test]
+    fn physical_dice_rolls_have_to_be_from_1_to_6() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-x:3475")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn physical_dice_rolls_have_to_be_enough_to_choose_the_word() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-x:6611")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+        assert!(error.to_string().contains("rolls greater than 4 are repeated"));
+    }
+
+    #[test]
+    fn physical_dice_rolls_could_be_used_for_a_single_password_only() {
+        let args = vec![s!("./dpg"), s!("-w:3"), s!("-p:3"), s!("-x:1234")];
+        let error = parse_command_line(args).unwrap_err();
+        assert!(error.to_string().contains("single password only (-p 1)"));
+    }
+
+    #[test]
+    fn password_policy_could_be_specified() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-r:max=40,digit")];
+        let options = parse_command_line(args).unwrap();
+        let expected_policy = Policy {
+            max_length: Some(40),
+            require_digit: true,
+            ..Policy::default()
         };
-        assert_eq!(options, expected_options);
+        assert_eq!(options.policy, Some(expected_policy));
     }
 
     #[test]
-    #[should_panic(expected = "4")]
-    fn too_many_options() {
+    fn password_policy_that_could_not_be_met_should_fail() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-r:max=10")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn max_characters_could_be_specified() {
+        let args = vec![s!("./dpg"), s!("-w:3"), s!("-m:32")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.max_characters, Some(32));
+    }
+
+    #[test]
+    fn max_characters_too_small_for_number_of_words_should_fail() {
+        let args = vec![s!("./dpg"), s!("-w:6"), s!("-m:16")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn template_determines_number_of_words() {
+        let args = vec![s!("./dpg"), s!("-t:{word}-{word}{digits:3}{symbol}")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.password_length, 2);
+        assert!(options.template.is_some());
+    }
+
+    #[test]
+    fn template_can_not_be_used_with_number_of_words() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-t:w.w.d2")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn template_has_to_contain_at_least_one_word() {
+        let args = vec![s!("./dpg"), s!("-t:d6")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn separator_set_could_be_specified() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-j:-_.!-")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.separator_set, Some(vec!['-', '_', '.', '!']));
+    }
+
+    #[test]
+    fn digits_could_be_used_as_separator_set() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-j:digits")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.separator_set, Some("0123456789".chars().collect()));
+    }
+
+    #[test]
+    fn empty_separator_could_be_specified() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-s:")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.separator, "");
+    }
+
+    #[test]
+    fn range_of_words_could_be_specified() {
+        let args = vec![s!("./dpg"), s!("-w:4-6")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.password_length, 4);
+        assert_eq!(options.password_length_max, Some(6));
+    }
+
+    #[test]
+    fn range_of_words_has_to_be_ordered() {
+        let args = vec![s!("./dpg"), s!("-w:6-4")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn range_of_words_could_not_exceed_255() {
+        let args = vec![s!("./dpg"), s!("-w:4-256")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn range_of_words_could_not_start_from_0() {
+        let args = vec![s!("./dpg"), s!("-w:0-5")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn passwords_could_be_generated_in_parallel() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-a")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.threads, Some(::parallel::available_threads()));
+        assert!(!options.unordered);
+
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("--parallel=3"), s!("--unordered")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.threads, Some(3));
+        assert!(options.unordered);
+
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-a:0")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn unique_words_and_passwords_could_be_requested() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-p:10"), s!("-n"), s!("-b")];
+        let options = parse_command_line(args).unwrap();
+        assert!(options.unique_words);
+        assert!(options.unique_passwords);
+    }
+
+    #[test]
+    fn more_unique_passwords_than_possible_could_not_be_requested() {
+        // only 6 words of the English list are at most 3 characters long
+        let args = vec![s!("./dpg"), s!("-w:1"), s!("-m:3"), s!("-p:10"), s!("-b")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn number_of_options_is_not_limited() {
         let args = vec![s!("./dpg"),
                         s!("-l:pl"),
                         s!("-w:9"),
                         s!("-p:6"),
                         s!("-s:."),
-                        s!("-c"),
-                        s!("-d"),
-                        s!("-h")];
-        let options = parse_command_line(args);
-        let expected_options = Options {
-            language: "pl".to_string(),
-            password_length: 9,
-            password_count: 6,
-            separator: ".".to_string(),
-            simulate_dices: true,
-            help: true,
-            clipboard: true,
+                        s!("-u:title"),
+                        s!("-n"),
+                        s!("-d")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.language, "pl");
+        assert_eq!(options.password_length, 9);
+        assert_eq!(options.password_count, 6);
+        assert_eq!(options.separator, ".");
+        assert_eq!(options.capitalization, Capitalization::Title);
+        assert!(options.unique_words && options.simulate_dices);
+    }
+
+    #[test]
+    fn help_with_other_options_is_reported() {
+        let args = vec![s!("./dpg"), s!("-w:9"), s!("-s:."), s!("-c"), s!("-d"), s!("-h")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error, DpgError::Help(None));
+    }
+
+    #[test]
+    fn long_options_are_supported() {
+        let args = vec![s!("./dpg"), s!("--words"), s!("5"), s!("--language=pl"), s!("--separator"), s!("_"), s!("--clipboard")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.password_length, 5);
+        assert_eq!(options.language, "pl");
+        assert_eq!(options.separator, "_");
+        assert!(options.clipboard);
+    }
+
+    #[test]
+    fn short_options_could_take_value_from_next_argument() {
+        let args = vec![s!("./dpg"), s!("-w"), s!("5"), s!("-p3"), s!("-s"), s!("-")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.password_length, 5);
+        assert_eq!(options.password_count, 3);
+        assert_eq!(options.separator, "-");
+    }
+
+    #[test]
+    fn short_flags_could_be_combined() {
+        let args = vec![s!("./dpg"), s!("-ndw"), s!("4")];
+        let options = parse_command_line(args).unwrap();
+        assert!(options.unique_words);
+        assert!(options.simulate_dices);
+        assert_eq!(options.password_length, 4);
+    }
+
+    #[test]
+    fn case_of_values_is_preserved() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("-s:X"), s!("-L:PL")];
+        let options = parse_command_line(args).unwrap();
+        assert_eq!(options.separator, "X");
+        assert_eq!(options.language, "pl");
+    }
+
+    #[test]
+    fn missing_value_is_reported() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("--separator")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+
+        let args = vec![s!("./dpg"), s!("-w"), s!("-p:2")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn flags_do_not_take_values() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("--clipboard=yes")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn closest_option_is_suggested() {
+        let args = vec![s!("./dpg"), s!("--wrods=5")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error, DpgError::UnknownOption(s!("--wrods"), Some(s!("--words"))));
+
+        let args = vec![s!("./dpg"), s!("--emoji")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error, DpgError::UnknownOption(s!("--emoji"), None));
+    }
+
+    #[test]
+    fn positional_arguments_are_rejected() {
+        let args = vec![s!("./dpg"), s!("-w:4"), s!("five")];
+        let error = parse_command_line(args).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn bare_options_are_the_same_as_gen_command() {
+        let bare = parse_command(vec![s!("./dpg"), s!("-w:5"), s!("-l:pl")]).unwrap();
+        let gen = parse_command(vec![s!("./dpg"), s!("gen"), s!("-w:5"), s!("-l:pl")]).unwrap();
+        assert_eq!(bare, gen);
+        match bare {
+            Command::Generate(options) => assert_eq!(options.password_length, 5),
+            command => panic!("unexpected command: {:?}", command),
+        }
+    }
+
+    #[test]
+    fn commands_have_their_own_help() {
+        let help = |args: &[&str]| parse_command(args.iter().map(|arg| arg.to_string()).collect()).unwrap_err();
+        assert_eq!(help(&["./dpg", "-h"]), DpgError::Help(None));
+        assert_eq!(help(&["./dpg", "help"]), DpgError::Help(None));
+        assert_eq!(help(&["./dpg", "help", "roll"]), DpgError::Help(Some(s!("roll"))));
+        assert_eq!(help(&["./dpg", "gen", "-h"]), DpgError::Help(Some(s!("gen"))));
+        assert_eq!(help(&["./dpg", "lookup", "--help"]), DpgError::Help(Some(s!("lookup"))));
+    }
+
+    #[test]
+    fn commands_accept_only_their_own_options() {
+        let error = parse_command(vec![s!("./dpg"), s!("lists"), s!("-w:5")]).unwrap_err();
+        assert_eq!(error, DpgError::UnknownOption(s!("-w"), None));
+
+        let error = parse_command(vec![s!("./dpg"), s!("roll"), s!("-w:5"), s!("--separatr=.")]).unwrap_err();
+        assert_eq!(error, DpgError::UnknownOption(s!("--separatr"), Some(s!("--separator"))));
+    }
+
+    #[test]
+    fn unknown_command_is_reported() {
+        let error = parse_command(vec![s!("./dpg"), s!("generate")]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn positional_arguments_are_passed_to_commands() {
+        let command = parse_command(vec![s!("./dpg"), s!("lookup"), s!("-l:PL"), s!("11111"), s!("zysk")]).unwrap();
+        assert_eq!(command, Command::Lookup { language: s!("pl"), keys: vec![s!("11111"), s!("zysk")] });
+
+        let command = parse_command(vec![s!("./dpg"), s!("check"), s!("--"), s!("-correct-horse-")]).unwrap();
+        assert_eq!(command, Command::Check { password: Some(s!("-correct-horse-")) });
+
+        let command = parse_command(vec![s!("./dpg"), s!("check")]).unwrap();
+        assert_eq!(command, Command::Check { password: None });
+    }
+
+    #[test]
+    fn language_of_messages_could_be_chosen_before_command() {
+        let parse = |args: &[&str]| parse_command(args.iter().map(|arg| arg.to_string()).collect());
+        assert_eq!(
+            parse(&["./dpg", "-g", "pl", "check", "--", "-correct-horse-"]),
+            Ok(Command::Check { password: Some(s!("-correct-horse-")) })
+        );
+        assert_eq!(parse(&["./dpg", "--ui-lang=pl", "-g:en", "lookup", "zysk"]), parse(&["./dpg", "lookup", "zysk"]));
+        assert_eq!(parse(&["./dpg", "-gpl", "help", "roll"]), Err(DpgError::Help(Some(s!("roll")))));
+        assert_eq!(parse(&["./dpg", "-g", "pl", "help"]), Err(DpgError::Help(None)));
+        assert_eq!(parse(&["./dpg", "-g", "pl", "-w:3"]), parse(&["./dpg", "gen", "-w:3", "-g", "pl"]));
+    }
+
+    #[test]
+    fn roll_needs_number_of_words() {
+        let command = parse_command(vec![s!("./dpg"), s!("roll"), s!("-w"), s!("4"), s!("-s:.")]).unwrap();
+        assert_eq!(command, Command::Roll { language: s!("en"), password_length: 4, separator: s!(".") });
+
+        let error = parse_command(vec![s!("./dpg"), s!("roll")]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn completions_need_a_supported_shell() {
+        let command = parse_command(vec![s!("./dpg"), s!("completions"), s!("zsh")]).unwrap();
+        assert_eq!(command, Command::Completions { shell: Shell::Zsh });
+        assert_eq!(parse_command(vec![s!("./dpg"), s!("man")]).unwrap(), Command::Man);
+
+        let error = parse_command(vec![s!("./dpg"), s!("completions"), s!("tcsh")]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+        let error = parse_command(vec![s!("./dpg"), s!("completions")]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn completed_values_are_valid() {
+        for spec in OPTION_SPECS.iter() {
+            if let Values::OneOf(values) = spec.values {
+                for value in values {
+                    assert_eq!(check_value(spec.short, value), Ok(()), "-{}:{}", spec.short, value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn help_is_generated_from_definitions() {
+        let help = help_message(None);
+        for command in COMMAND_SPECS.iter() {
+            assert!(help.contains(command.name));
+        }
+        assert!(help.contains("-w, --words <number>"));
+
+        let help = help_message(Some("lookup"));
+        assert!(help.contains("usage: dpg lookup [options] <rolls|word>..."));
+        assert!(help.contains("-l, --language <language>"));
+        assert!(!help.contains("--words"));
+    }
+
+    fn team_config() -> Config {
+        Config::parse("language = \"pl\"\n\
+                       words = 6\n\
+                       separator = \".\"\n\
+                       [profile.wifi]\n\
+                       words = 4\n\
+                       case = \"title\"\n\
+                       [profile.pin]\n\
+                       template = \"w.d4\"\n", "config.toml").unwrap()
+    }
+
+    fn parse_with_team_config(args: &[&str]) -> Result<Options, DpgError> {
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        match parse_command_with_defaults(args, Some(&team_config()), &HashMap::new())? {
+            Command::Generate(options) => Ok(*options),
+            command => panic!("unexpected command: {:?}", command),
+        }
+    }
+
+    #[test]
+    fn config_defaults_are_used() {
+        let options = parse_with_team_config(&["./dpg", "gen"]).unwrap();
+        assert_eq!(options.language, "pl");
+        assert_eq!(options.password_length, 6);
+        assert_eq!(options.separator, ".");
+    }
+
+    #[test]
+    fn profile_overrides_defaults_and_command_line_overrides_profile() {
+        let options = parse_with_team_config(&["./dpg", "--profile", "wifi"]).unwrap();
+        assert_eq!(options.password_length, 4);
+        assert_eq!(options.capitalization, Capitalization::Title);
+        assert_eq!(options.language, "pl");
+
+        let options = parse_with_team_config(&["./dpg", "--profile=wifi", "-w:5", "-l:en"]).unwrap();
+        assert_eq!(options.password_length, 5);
+        assert_eq!(options.language, "en");
+        assert_eq!(options.capitalization, Capitalization::Title);
+    }
+
+    #[test]
+    fn conflicting_options_of_lower_layers_are_dropped() {
+        // template of the profile replaces number of words and separator of the defaults
+        let options = parse_with_team_config(&["./dpg", "-f", "pin"]).unwrap();
+        assert!(options.template.is_some());
+        assert_eq!(options.password_length, 1);
+
+        // target entropy given on the command line replaces number of words of the defaults
+        let options = parse_with_team_config(&["./dpg", "-e:40"]).unwrap();
+        assert_eq!(options.password_length, 4);
+    }
+
+    #[test]
+    fn unknown_profile_is_reported() {
+        let error = parse_with_team_config(&["./dpg", "-f:master"]).unwrap_err();
+        assert_eq!(error.exit_code(), 6);
+
+        let error = parse_command_line(vec![s!("./dpg"), s!("-w:4"), s!("-f:wifi")]).unwrap_err();
+        assert_eq!(error.exit_code(), 6);
+    }
+
+    #[test]
+    fn config_applies_only_to_options_of_the_command() {
+        let args = vec![s!("./dpg"), s!("lookup"), s!("11111")];
+        let command = parse_command_with_defaults(args, Some(&team_config()), &HashMap::new()).unwrap();
+        assert_eq!(command, Command::Lookup { language: s!("pl"), keys: vec![s!("11111")] });
+    }
+
+    #[test]
+    fn single_values_are_checked() {
+        assert!(check_value('w', "4-6").is_ok());
+        assert!(check_value('w', "6-4").is_err());
+        assert!(check_value('p', "0").is_ok());
+        assert!(check_value('p', "-1").is_err());
+        assert!(check_value('u', "title").is_ok());
+        assert!(check_value('u', "random").is_err());
+        assert!(check_value('l', "xx").is_err());
+    }
+
+    fn parse_with_env(args: &[&str], env: &[(&str, &str)]) -> Result<Command, DpgError> {
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        let env = env.iter().map(|&(name, value)| (name.to_string(), value.to_string())).collect();
+        parse_command_with_defaults(args, Some(&team_config()), &env)
+    }
+
+    #[test]
+    fn environment_overrides_config_and_command_line_overrides_environment() {
+        let command = parse_with_env(&["./dpg", "-f:wifi"], &[("DPG_WORDS", "7"), ("DPG_LANG", "EN"), ("DPG_DICE", "yes")]).unwrap();
+        match command {
+            Command::Generate(options) => {
+                assert_eq!(options.password_length, 7);
+                assert_eq!(options.language, "en");
+                assert!(options.simulate_dices);
+                assert_eq!(options.capitalization, Capitalization::Title);
+            }
+            command => panic!("unexpected command: {:?}", command),
+        }
+
+        let command = parse_with_env(&["./dpg", "-w:3", "-s:_"], &[("DPG_WORDS", "7"), ("DPG_SEPARATOR", "")]).unwrap();
+        match command {
+            Command::Generate(options) => {
+                assert_eq!(options.password_length, 3);
+                assert_eq!(options.separator, "_");
+            }
+            command => panic!("unexpected command: {:?}", command),
+        }
+    }
+
+    #[test]
+    fn profile_could_be_selected_using_environment() {
+        let command = parse_with_env(&["./dpg", "gen"], &[("DPG_PROFILE", "wifi")]).unwrap();
+        assert!(matches!(command, Command::Generate(ref options) if options.password_length == 4));
+    }
+
+    #[test]
+    fn invalid_environment_values_are_reported() {
+        let error = parse_with_env(&["./dpg", "gen"], &[("DPG_COUNT", "many")]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+        assert!(error.to_string().starts_with("invalid value of DPG_COUNT: "));
+
+        let error = parse_with_env(&["./dpg", "gen"], &[("DPG_CLIPBOARD", "maybe")]).unwrap_err();
+        assert_eq!(error.to_string(), "invalid value of DPG_CLIPBOARD: 'maybe' (expected 1 or 0)");
+
+        // values valid on their own are validated together as well
+        let error = parse_with_env(&["./dpg", "gen"], &[("DPG_EXTRA", "3425"), ("DPG_COUNT", "2")]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn effective_settings_are_shown_with_their_sources() {
+        let command = parse_with_env(&["./dpg", "--show-config", "-f", "wifi", "-p:2"], &[("DPG_LANG", "en")]).unwrap();
+        let settings = match command {
+            Command::ShowConfig(settings) => settings,
+            command => panic!("unexpected command: {:?}", command),
         };
-        assert_eq!(options, expected_options);
+        let setting = |name: &str| settings.iter().find(|s| s.0 == name).cloned().unwrap();
+        assert_eq!(setting("language"), ("language", s!("'en'"), Source::Environment("DPG_LANG")));
+        assert_eq!(setting("words"), ("words", s!("'4'"), Source::Profile(s!("wifi"), s!("config.toml"), 5)));
+        assert_eq!(setting("separator"), ("separator", s!("'.'"), Source::Config(s!("config.toml"), 3)));
+        assert_eq!(setting("count"), ("count", s!("'2'"), Source::CommandLine));
+        assert_eq!(setting("dice"), ("dice", s!("false"), Source::Default));
+    }
+
+    #[test]
+    fn output_format_could_be_specified() {
+        let options = parse_command_line(vec![s!("./dpg"), s!("-w:4"), s!("--format=JSON")]).unwrap();
+        assert_eq!(options.format, OutputFormat::Json);
+
+        let options = parse_command_line(vec![s!("./dpg"), s!("-w:4")]).unwrap();
+        assert_eq!(options.format, OutputFormat::Text);
+
+        let error = parse_command_line(vec![s!("./dpg"), s!("-w:4"), s!("-o:xml")]).unwrap_err();
+        assert_eq!(error.exit_code(), 2);
     }
 }
@@ -0,0 +1,248 @@
+//! Formats generated passwords: human readable text (the default), or machine readable output for scripts.
+//!
+//! Every machine readable record contains the password, its words, the list words are taken from, number of words,
+//! separator, separators chosen from a set and entropy (in bits). Separator is null (empty in CSV) if separators are
+//! chosen from a set (they are listed in order then), or template is used. Only the words taken from the list are
+//! the words of a password generated using a template: its digits, symbols and text are not included.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use messages::{Message, UiLanguage};
+use password::Password;
+use strength::PasswordStrength;
+
+/// Format generated passwords are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    /// header, one password per line and strength report
+    #[default]
+    Text,
+    /// one password per line, nothing else
+    Plain,
+    /// JSON array of records
+    Json,
+    /// one JSON record per line
+    Ndjson,
+    /// CSV with a header line
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Message;
+
+    fn from_str(format: &str) -> Result<OutputFormat, Message> {
+        match format.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "plain" => Ok(OutputFormat::Plain),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(Message::new("error.unknown_format", &[&format])),
+        }
+    }
+}
+
+const CSV_HEADER: &str = "password,words,list,word_count,separator,separators,entropy";
+
+/// Writes passwords one by one: `write` for every password, then `finish` (which writes everything that follows the passwords).
+pub struct PasswordWriter<W: Write> {
+    out: W,
+    format: OutputFormat,
+    language: UiLanguage,
+    written: usize,
+}
+
+impl<W: Write> PasswordWriter<W> {
+    pub fn new(out: W, format: OutputFormat) -> PasswordWriter<W> {
+        PasswordWriter { out, format, language: UiLanguage::En, written: 0 }
+    }
+
+    /// Writes header and strength report of `OutputFormat::Text` in the given language (English by default).
+    pub fn with_language(mut self, language: UiLanguage) -> PasswordWriter<W> {
+        self.language = language;
+        self
+    }
+
+    pub fn write(&mut self, password: &Password) -> io::Result<()> {
+        let first = self.written == 0;
+        self.written += 1;
+        match self.format {
+            OutputFormat::Text if first => writeln!(self.out, "{}{}", self.language.text("generated"), password),
+            OutputFormat::Text | OutputFormat::Plain => writeln!(self.out, "{}", password),
+            OutputFormat::Json => write!(self.out, "{}\n  {}", if first { "[" } else { "," }, json_record(password)),
+            OutputFormat::Ndjson => writeln!(self.out, "{}", json_record(password)),
+            OutputFormat::Csv if first => write!(self.out, "{}\n{}\n", CSV_HEADER, csv_record(password)),
+            OutputFormat::Csv => writeln!(self.out, "{}", csv_record(password)),
+        }
+    }
+
+    /// Completes the output: strength report (`Text`), closing bracket (`Json`), or header if no passwords have been written (`Csv`).
+    pub fn finish(&mut self, strength: &PasswordStrength) -> io::Result<()> {
+        match (self.format, self.written) {
+            (OutputFormat::Text, 0) => write!(
+                self.out,
+                "{}\n{}",
+                self.language.text("generated"),
+                ::strength::format_strength_in(strength, self.language)
+            )?,
+            (OutputFormat::Text, _) => write!(self.out, "\n{}", ::strength::format_strength_in(strength, self.language))?,
+            (OutputFormat::Json, 0) => self.out.write_all(b"[]\n")?,
+            (OutputFormat::Json, _) => self.out.write_all(b"\n]\n")?,
+            (OutputFormat::Csv, 0) => writeln!(self.out, "{}", CSV_HEADER)?,
+            _ => {}
+        }
+        self.out.flush()
+    }
+}
+
+fn json_record(password: &Password) -> String {
+    let words = password.words.iter().map(|word| json_string(word)).collect::<Vec<String>>();
+    let separators = password.separators.iter().map(|separator| json_string(&separator.to_string())).collect::<Vec<String>>();
+    format!(
+        "{{\"password\":{},\"words\":[{}],\"list\":{},\"word_count\":{},\"separator\":{},\"separators\":[{}],\"entropy\":{:.2}}}",
+        json_string(password.as_str()),
+        words.join(","),
+        json_string(&password.list_id),
+        password.words.len(),
+        password.separator.as_ref().map_or_else(|| s!("null"), |separator| json_string(separator)),
+        separators.join(","),
+        password.entropy.bits()
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Words are separated with spaces (words never contain whitespace), separators chosen from a set are not separated
+/// (every one of them is a single character).
+fn csv_record(password: &Password) -> String {
+    format!(
+        "{},{},{},{},{},{},{:.2}",
+        csv_field(password.as_str()),
+        csv_field(&password.words.join(" ")),
+        csv_field(&password.list_id),
+        password.words.len(),
+        csv_field(password.separator.as_ref().map_or("", |separator| separator.as_str())),
+        csv_field(&password.separators.iter().collect::<String>()),
+        password.entropy.bits()
+    )
+}
+
+fn csv_field(text: &str) -> String {
+    match text.contains(&[',', '"', '\n', '\r'][..]) || text.trim() != text {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use super::*;
+
+    fn password(rendered: &str, separator: &str) -> Password {
+        Password {
+            words: vec![s!("correct"), s!("horse")],
+            indices: vec![12, 345],
+            separator: Some(separator.to_string()),
+            separators: Vec::new(),
+            list_id: s!("en"),
+            entropy: PasswordStrength::from_words(1296, 2),
+            rendered: rendered.to_string(),
+        }
+    }
+
+    fn write_all(format: OutputFormat, passwords: &[Password]) -> String {
+        let mut out: Vec<u8> = Vec::new();
+        {
+            let mut writer = PasswordWriter::new(&mut out, format);
+            for password in passwords {
+                writer.write(password).unwrap();
+            }
+            writer.finish(&PasswordStrength::from_words(1296, 2)).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn formats_are_parsed_case_insensitively() {
+        assert_eq!("JSON".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert_eq!("ndjson".parse::<OutputFormat>(), Ok(OutputFormat::Ndjson));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn plain_output_contains_passwords_only() {
+        let passwords = [password("correct-horse", "-"), password("correct.horse", ".")];
+        assert_eq!(write_all(OutputFormat::Plain, &passwords), "correct-horse\ncorrect.horse\n");
+    }
+
+    #[test]
+    fn text_output_contains_header_and_strength() {
+        let output = write_all(OutputFormat::Text, &[password("correct-horse", "-")]);
+        assert!(output.starts_with("generated password(s):\ncorrect-horse\n\nentropy: 20.7 bits\n"));
+    }
+
+    #[test]
+    fn text_output_is_written_in_given_language() {
+        let mut out: Vec<u8> = Vec::new();
+        {
+            let mut writer = PasswordWriter::new(&mut out, OutputFormat::Text).with_language(UiLanguage::Pl);
+            writer.write(&password("correct-horse", "-")).unwrap();
+            writer.finish(&PasswordStrength::from_words(1296, 2)).unwrap();
+        }
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.starts_with("wygenerowane hasła:\ncorrect-horse\n\nentropia (w bitach): 20.7\n"));
+    }
+
+    #[test]
+    fn json_output_is_an_array_of_records() {
+        let passwords = [password("correct-horse", "-"), password("correct\"horse", "\"")];
+        assert_eq!(
+            write_all(OutputFormat::Json, &passwords),
+            "[\n  {\"password\":\"correct-horse\",\"words\":[\"correct\",\"horse\"],\"list\":\"en\",\"word_count\":2,\"separator\":\"-\",\"separators\":[],\"entropy\":20.68},\n  \
+             {\"password\":\"correct\\\"horse\",\"words\":[\"correct\",\"horse\"],\"list\":\"en\",\"word_count\":2,\"separator\":\"\\\"\",\"separators\":[],\"entropy\":20.68}\n]\n"
+        );
+        assert_eq!(write_all(OutputFormat::Json, &[]), "[]\n");
+    }
+
+    #[test]
+    fn separators_chosen_from_set_are_listed() {
+        let password = Password { separator: None, separators: vec!['.', '"'], ..password("correct.horse\"staple", "") };
+        assert!(json_record(&password).contains(",\"separator\":null,\"separators\":[\".\",\"\\\"\"],"));
+        assert!(csv_record(&password).ends_with(",2,,\".\"\"\",20.68"));
+    }
+
+    #[test]
+    fn ndjson_output_contains_record_per_line() {
+        let output = write_all(OutputFormat::Ndjson, &[password("a-b", "-"), password("c-d", "-")]);
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.starts_with('{') && line.ends_with('}')));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        let output = write_all(OutputFormat::Csv, &[password("correct,horse", ","), password("correct horse", " ")]);
+        assert_eq!(
+            output,
+            "password,words,list,word_count,separator,separators,entropy\n\
+             \"correct,horse\",correct horse,en,2,\",\",,20.68\n\
+             correct horse,correct horse,en,2,\" \",,20.68\n"
+        );
+    }
+}
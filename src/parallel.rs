@@ -0,0 +1,222 @@
+//! Generates passwords on several threads at once. Every worker thread generates batches of passwords using its own
+//! generator (ChaCha20 seeded from the operating system's crypto secure generator, see `dices::secure_rng`),
+//! and batches are passed on (on the calling thread) in the order they have been started in, or as soon as they are ready.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use messages::Message;
+use password::Password;
+use passwords::PreparedOptions;
+
+/// Number of passwords a worker generates before passing them on.
+const BATCH_SIZE: usize = 256;
+
+/// Number of batches passed on in order, workers do not start batches too far ahead of it - so batches waiting
+/// for the ones started earlier could not pile up.
+#[rustfmt::skip]
+struct Progress {
+    /// `STOPPED` if no more batches are needed
+    passed:     Mutex<usize>,
+    changed:    Condvar,
+}
+
+const STOPPED: usize = usize::MAX;
+
+impl Progress {
+    fn new() -> Progress {
+        Progress { passed: Mutex::new(0), changed: Condvar::new() }
+    }
+
+    /// Waits until `batch` is less than `ahead` batches after the ones passed on, returns false if no more batches are needed.
+    fn wait_for(&self, batch: usize, ahead: usize) -> bool {
+        let mut passed = self.passed.lock().unwrap_or_else(|error| error.into_inner());
+        while batch >= passed.saturating_add(ahead) {
+            passed = self.changed.wait(passed).unwrap_or_else(|error| error.into_inner());
+        }
+        *passed != STOPPED
+    }
+
+    fn set(&self, passed: usize) {
+        *self.passed.lock().unwrap_or_else(|error| error.into_inner()) = passed;
+        self.changed.notify_all();
+    }
+}
+
+/// Stops the workers waiting for progress once the batches are no longer received (even if consuming them panicked).
+struct StopOnDrop<'a>(&'a Progress);
+
+impl<'a> Drop for StopOnDrop<'a> {
+    fn drop(&mut self) {
+        self.0.set(STOPPED);
+    }
+}
+
+/// Number of threads passwords are generated by if not specified: one for every CPU.
+pub fn available_threads() -> usize {
+    thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1)
+}
+
+/// Generates `count` passwords (or an endless stream, if `count` is None) using `threads` worker threads,
+/// and passes them to `consume` until it returns false or fails.
+///
+/// Memory use does not depend on the number of passwords: workers wait (when a few batches are ready) until
+/// the batches are consumed, and they stop as soon as no more passwords are needed. If batches are passed on in order,
+/// workers do not start batches more than `threads * 2` after the last one passed on, so at most that many batches wait.
+pub(crate) fn generate<F>(prepared: &PreparedOptions, count: Option<usize>, threads: usize, ordered: bool, consume: F) -> io::Result<()>
+    where F: FnMut(Password) -> io::Result<bool> {
+    let batches = count.map(|count| count.div_ceil(BATCH_SIZE));
+    let next_batch = AtomicUsize::new(0);
+    let progress = Progress::new();
+    let (sender, receiver) = mpsc::sync_channel::<(usize, Result<Vec<Password>, Message>)>(threads * 2);
+
+    thread::scope(|scope| {
+        for _i in 0..threads {
+            let sender = sender.clone();
+            let next_batch = &next_batch;
+            let progress = &progress;
+            scope.spawn(move || {
+                ::dices::with_rng(::dices::secure_rng(), || loop {
+                    let batch = next_batch.fetch_add(1, Ordering::Relaxed);
+                    if batches.is_some_and(|batches| batch >= batches) || (ordered && !progress.wait_for(batch, threads * 2)) {
+                        break;
+                    }
+                    let size = count.map_or(BATCH_SIZE, |count| BATCH_SIZE.min(count - batch * BATCH_SIZE));
+                    let passwords = (0..size).map(|_i| prepared.generate()).collect::<Result<Vec<Password>, Message>>();
+                    let failed = passwords.is_err();
+                    // receiver is gone if no more passwords are needed
+                    if sender.send((batch, passwords)).is_err() || failed {
+                        break;
+                    }
+                });
+            });
+        }
+        drop(sender);
+
+        // receiver is dropped when all the passwords needed are consumed, which stops the workers
+        let _stop = StopOnDrop(&progress);
+        receive(receiver, ordered, &progress, consume)
+    })
+}
+
+/// Passes on passwords received from the workers, the first batch that failed to be generated fails all of them.
+fn receive<F>(receiver: Receiver<(usize, Result<Vec<Password>, Message>)>, ordered: bool, progress: &Progress, mut consume: F)
+    -> io::Result<()> where F: FnMut(Password) -> io::Result<bool> {
+    let mut pending: HashMap<usize, Vec<Password>> = HashMap::new();
+    let mut passed = 0;
+    for (batch, passwords) in receiver {
+        let passwords = passwords.map_err(::passwords::invalid_options)?;
+        let ready = match ordered {
+            false => vec![passwords],
+            true => {
+                pending.insert(batch, passwords);
+                let mut ready = Vec::new();
+                while let Some(passwords) = pending.remove(&passed) {
+                    ready.push(passwords);
+                    passed += 1;
+                }
+                ready
+            }
+        };
+        if ordered {
+            progress.set(passed);
+        }
+        for password in ready.into_iter().flatten() {
+            if !consume(password)? {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use super::*;
+    use option_parser::Options;
+    use strength::PasswordStrength;
+
+    fn prepared() -> PreparedOptions {
+        let args = vec![s!("./dpg"), s!("-w:3")];
+        let options: Options = ::option_parser::parse_command_line(args).unwrap();
+        PreparedOptions::new(&options, &::diceware_info::build_diceware_repository())
+    }
+
+    fn password(word: &str) -> Password {
+        Password {
+            words: vec![word.to_string()],
+            indices: vec![0],
+            separator: Some(s!("-")),
+            separators: Vec::new(),
+            list_id: s!("en"),
+            entropy: PasswordStrength::from_words(1296, 1),
+            rendered: word.to_string(),
+        }
+    }
+
+    #[test]
+    fn batches_are_passed_on_in_order_they_have_been_started_in() {
+        for &(ordered, expected) in &[(true, ["a", "b", "c"]), (false, ["c", "a", "b"])] {
+            let (sender, receiver) = mpsc::sync_channel(3);
+            for &(batch, word) in &[(2, "c"), (0, "a"), (1, "b")] {
+                sender.send((batch, Ok(vec![password(word)]))).unwrap();
+            }
+            drop(sender);
+
+            let mut passwords = Vec::new();
+            receive(receiver, ordered, &Progress::new(), |password| {
+                passwords.push(password.rendered);
+                Ok(true)
+            }).unwrap();
+            assert_eq!(passwords, expected);
+        }
+    }
+
+    #[test]
+    fn workers_wait_until_earlier_batches_are_passed_on() {
+        let progress = Progress::new();
+        assert!(progress.wait_for(3, 4));
+        thread::scope(|scope| {
+            let waiting = scope.spawn(|| progress.wait_for(5, 4));
+            progress.set(2);
+            assert!(waiting.join().unwrap());
+            let waiting = scope.spawn(|| progress.wait_for(100, 4));
+            progress.set(STOPPED);
+            assert!(!waiting.join().unwrap());
+        });
+    }
+
+    #[test]
+    fn requested_number_of_passwords_is_generated() {
+        let prepared = prepared();
+        for &(threads, ordered) in &[(1, true), (3, true), (4, false)] {
+            let mut passwords = Vec::new();
+            generate(&prepared, Some(1000), threads, ordered, |password| {
+                passwords.push(password);
+                Ok(true)
+            }).unwrap();
+            assert_eq!(passwords.len(), 1000);
+            assert!(passwords.iter().all(|password| password.words.len() == 3));
+        }
+    }
+
+    #[test]
+    fn generation_stops_when_no_more_passwords_are_needed() {
+        let prepared = prepared();
+        let mut consumed = 0;
+        generate(&prepared, None, 4, true, |_password| {
+            consumed += 1;
+            Ok(consumed < 5000)
+        }).unwrap();
+        assert_eq!(consumed, 5000);
+
+        let error = generate(&prepared, None, 4, false, |_password| {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "reader has gone"))
+        }).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
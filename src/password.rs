@@ -0,0 +1,84 @@
+use std::fmt;
+
+use strength::PasswordStrength;
+
+/// Single generated password along with the data it has been generated from.
+///
+/// `Display` prints the password itself, while `Debug` redacts everything that could reveal it,
+/// so passwords do not leak into logs by accident.
+#[rustfmt::skip]
+#[derive(Clone, PartialEq)]
+pub struct Password {
+    /// words the password consists of, as they appear in the word list (before capitalization, extra characters, etc.)
+    pub words:      Vec<String>,
+    /// indices of the words in the word list
+    pub indices:    Vec<usize>,
+    /// separator words are joined with (None if separators are randomly chosen from a set, or template is used)
+    pub separator:  Option<String>,
+    /// separators randomly chosen from a set, one between every two words (empty if not chosen from a set)
+    pub separators: Vec<char>,
+    /// id (language) of the word list words are taken from
+    pub list_id:    String,
+    /// strength of the password
+    pub entropy:    PasswordStrength,
+    /// password ready to be used
+    pub rendered:   String,
+}
+
+impl Password {
+    pub fn as_str(&self) -> &str {
+        &self.rendered
+    }
+}
+
+impl fmt::Display for Password {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.rendered)
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Password")
+            .field("words", &format_args!("<{} redacted>", self.words.len()))
+            .field("indices", &format_args!("<redacted>"))
+            .field("separator", &self.separator)
+            .field("separators", &format_args!("<redacted>"))
+            .field("list_id", &self.list_id)
+            .field("entropy", &self.entropy)
+            .field("rendered", &format_args!("<redacted>"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod password_tests {
+    use super::*;
+
+    fn password() -> Password {
+        Password {
+            words: vec![s!("correct"), s!("horse")],
+            indices: vec![12, 345],
+            separator: Some(s!("-")),
+            separators: Vec::new(),
+            list_id: s!("en"),
+            entropy: PasswordStrength::from_words(1296, 2),
+            rendered: s!("Correct-Horse"),
+        }
+    }
+
+    #[test]
+    fn display_prints_the_password() {
+        assert_eq!(password().to_string(), "Correct-Horse");
+    }
+
+    #[test]
+    fn debug_does_not_reveal_the_password() {
+        let debug = format!("{:?}", password());
+        assert!(!debug.to_lowercase().contains("correct"));
+        assert!(!debug.to_lowercase().contains("horse"));
+        assert!(!debug.contains("345"));
+        assert!(debug.contains("<2 redacted>"));
+        assert!(debug.contains("\"en\""));
+    }
+}
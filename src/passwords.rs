@@ -2,10 +2,29 @@ extern crate clipboard;
 
 use self::clipboard::ClipboardContext;
 use self::clipboard::ClipboardProvider;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufWriter, Write};
 use std::{thread, time};
 
+use capitalization::{Capitalization, Choice};
 use diceware_info::DicewareInfo;
+use extra_security::{ExtraSecurity, PhysicalDice, SimulatedDice};
+use messages::Message;
 use option_parser::Options;
+use output::PasswordWriter;
+use password::Password;
+use policy::{Policy, PolicyPlan};
+use strength::{distinct_words_entropy, PasswordStrength};
+use template::Template;
+
+/// Password(s) generated along with the strength of each of them.
+/// All passwords generated at once share the same strength, as they are generated using the same options
+/// (unless their length is chosen randomly - then `strength` is the strength of the weakest possible password).
+#[rustfmt::skip]
+pub struct GeneratedPasswords {
+    pub passwords:  Vec<Password>,
+    pub strength:   PasswordStrength,
+}
 
 
 /// Main function to be called to generate passwords. It requires properly filled Options structue and repository of diceware word lists (which is collection of DicewareInfo structues).
@@ -19,26 +38,304 @@ use option_parser::Options;
 ///        password_length: 6,             // 6 words per password
 ///        password_count: 3,              // generate 3 passwords
 ///        separator: "-".to_string(),     // separate words by dash ('-')
-///        simulate_dices: false,          // do not simulate dice roll
-///        clipboard: false,               // do not copy passwords to clipboard
-///        help: false                     // do not call for help/usage
+///        ..Default::default()            // defaults of the command line for all the other options
 ///    };
 ///
 ///    let repository = dpg::diceware_info::build_diceware_repository();
-///    let passwords = dpg::passwords::generate_diceware_passwords(&options, repository);
+///    let passwords = dpg::passwords::generate_diceware_passwords(&options, repository)
+///        .expect("passwords could not be generated using the options given");
+///    for password in &passwords {
+///        println!("{}", password);
+///    }
 /// ```
+///
+/// Options are validated first (see `option_parser::check_options`), the description of the problem is returned
+/// if passwords could not be generated using them - also if physical dice rolls run out while generating.
 pub fn generate_diceware_passwords(
     options: &Options,
     diceware_repository: Vec<DicewareInfo>,
-) -> String {
-    let passwords = generate_all_passwords(options, diceware_repository);
+) -> Result<Vec<Password>, Message> {
+    ::option_parser::check_options(options)?;
+    let passwords = generate_all_passwords(options, &diceware_repository)
+        .map_err(|error| invalid_options_message(error).unwrap_or_else(|error| Message::new("error.output", &[&error])))?;
     // TODO: this should probably be moved from here,
     // it is not responsibility of the library itself
     // to copy generated passwords into clipboard
     if options.clipboard {
-        copy_to_clipboard(passwords.clone());
+        copy_to_clipboard(join_passwords(&passwords));
+    }
+    Ok(passwords)
+}
+
+/// Joins passwords into a single string, one password per line.
+pub fn join_passwords(passwords: &[Password]) -> String {
+    passwords.iter()
+        .map(|password| password.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Works exactly as generate_diceware_passwords, but additionally returns strength (entropy) of generated passwords.
+pub fn generate_diceware_passwords_with_strength(
+    options: &Options,
+    diceware_repository: Vec<DicewareInfo>,
+) -> Result<GeneratedPasswords, Message> {
+    let strength = calculate_strength(options, &diceware_repository);
+    let passwords = generate_diceware_passwords(options, diceware_repository)?;
+
+    Ok(GeneratedPasswords { passwords, strength })
+}
+
+/// Generates passwords and writes each of them (in `options.format`) as soon as it is generated, so the memory used
+/// does not depend on the number of passwords (unless passwords should not repeat - then all of them are remembered).
+/// If `options.password_count` is 0 (`UNLIMITED_PASSWORD_COUNT`), passwords are generated until writing fails,
+/// i.e. the output is closed - then the error (`BrokenPipe`) is returned, so the caller decides if it is a failure.
+/// Output is buffered (not every password is written separately), and flushed once all the passwords are written.
+///
+/// Options are neither validated nor copied to clipboard (see `generate_diceware_passwords`).
+pub fn write_passwords<W: Write>(options: &Options, diceware_repository: &[DicewareInfo], out: W) -> io::Result<()> {
+    let mut writer = PasswordWriter::new(BufWriter::new(out), options.format).with_language(options.ui_language);
+    generate_passwords(options, diceware_repository, |password| writer.write(&password))?;
+    writer.finish(&calculate_strength(options, diceware_repository))
+}
+
+/// Generates passwords (in parallel, if `options.threads` is specified) and passes them to `consume` one by one,
+/// until `options.password_count` passwords are passed (or `consume` fails, if the number is unlimited).
+/// Passwords that repeat are not passed if passwords should be unique, so it fails (instead of rejecting them forever)
+/// if there are not enough possible passwords, or words to choose from.
+fn generate_passwords<F>(options: &Options, diceware_repository: &[DicewareInfo], mut consume: F) -> io::Result<()>
+    where F: FnMut(Password) -> io::Result<()> {
+    let unlimited = options.password_count == ::option_parser::UNLIMITED_PASSWORD_COUNT;
+    if options.unique_passwords && unlimited {
+        return Err(invalid_options(Message::new("error.unlimited_unique", &[])));
+    }
+    if options.unique_words || options.unique_passwords {
+        validate_uniqueness(options, diceware_repository).map_err(invalid_options)?;
+    }
+    let prepared = PreparedOptions::new(options, diceware_repository);
+    let mut generated: HashSet<String> = HashSet::new();
+    let mut passed = 0;
+    let mut accept = |password: Password| -> io::Result<bool> {
+        if options.unique_passwords && !generated.insert(password.rendered.clone()) {
+            return Ok(true);
+        }
+        consume(password)?;
+        passed += 1;
+        Ok(unlimited || passed < options.password_count)
+    };
+
+    match options.threads {
+        Some(threads) => {
+            // exactly the number of passwords needed is generated, unless some of them could be rejected
+            let count = match unlimited || options.unique_passwords {
+                true => None,
+                false => Some(options.password_count),
+            };
+            ::parallel::generate(&prepared, count, threads, !options.unordered, accept)
+        }
+        None => {
+            while accept(prepared.generate().map_err(invalid_options)?)? {}
+            Ok(())
+        }
+    }
+}
+
+/// Error returned (instead of writing passwords) if passwords could not be generated using the options given.
+/// The message could be taken back from the error (`into_inner`), so it is reported in the language of the user interface.
+pub(crate) fn invalid_options(message: Message) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Message of the error returned by `invalid_options`, or the error itself if it is not one of them.
+pub(crate) fn invalid_options_message(error: io::Error) -> Result<Message, io::Error> {
+    if !error.get_ref().is_some_and(|inner| inner.is::<Message>()) {
+        return Err(error);
+    }
+    let inner = error.into_inner().expect("error has been checked to have a message");
+    Ok(*inner.downcast::<Message>().expect("error has been checked to have a message"))
+}
+
+/// Calculates strength of a single password that would be generated using specified options.
+/// If password policy is specified, but could not be met - strength of 0 bits is returned.
+///
+/// If length of passwords is chosen randomly from a range, the strength of the weakest possible password is returned (lower bound).
+pub fn calculate_strength(options: &Options, diceware_repository: &[DicewareInfo]) -> PasswordStrength {
+    if let Some(lengths) = password_length_range(options) {
+        return lengths
+            .map(|password_length| calculate_strength(&with_password_length(options, password_length), diceware_repository))
+            .fold(PasswordStrength { words: f64::INFINITY, extra: 0.0 }, |weakest, strength| {
+                match strength.bits() < weakest.bits() {
+                    true => strength,
+                    false => weakest,
+                }
+            });
+    }
+    let info = word_list(options, diceware_repository);
+    let password_length = options.password_length;
+
+    if let Some(ref template) = options.template {
+        let mut strength = PasswordStrength::from_words(info.words.len(), 0);
+        strength.words = template.entropy(info.words.len());
+        if options.unique_words {
+            strength.words += distinct_words_entropy(info.words.len(), template.word_count())
+                - PasswordStrength::from_words(info.words.len(), template.word_count()).words;
+        }
+        strength.extra = options.capitalization.entropy(password_length, &info.words);
+        return strength;
+    }
+    let mut strength = match effective_policy(options) {
+        None => {
+            let mut strength = words_strength(options, info.words.len());
+            strength.extra += options.capitalization.entropy(password_length, &info.words);
+            strength
+        },
+        Some(ref policy) => match policy.plan(options, &info.words) {
+            Ok(plan) => {
+                let mut strength = words_strength(options, plan.words.len());
+                strength.words += plan.length_probability.log2();
+                strength.extra += plan.capitalization.entropy(password_length, &plan.words);
+                strength.extra += plan.decoration_entropy();
+                strength
+            },
+            Err(_) => return PasswordStrength { words: 0.0, extra: 0.0 },
+        },
+    };
+    if options.extra_security != ExtraSecurity::Disabled {
+        strength.extra += ::extra_security::extra_character_entropy(password_length, &info.words);
+    }
+    if let Some(ref separator_set) = options.separator_set {
+        strength.extra += ::strength::word_entropy(separator_set.len()) * password_length.saturating_sub(1) as f64;
+    }
+    strength
+}
+
+/// Strength of words only: words are chosen either with or without replacement (if they should not repeat).
+fn words_strength(options: &Options, list_size: usize) -> PasswordStrength {
+    match options.unique_words {
+        true => PasswordStrength { words: distinct_words_entropy(list_size, options.password_length), extra: 0.0 },
+        false => PasswordStrength::from_words(list_size, options.password_length),
     }
-    passwords
+}
+
+/// Checks if distinct words (within a password) and distinct passwords (within a batch) could be generated using specified options.
+/// Every password is generated independently, so the number of possible passwords is estimated using their (lower bound) entropy.
+pub fn validate_uniqueness(options: &Options, diceware_repository: &[DicewareInfo]) -> Result<(), Message> {
+    if options.unique_words {
+        let list_size = word_list(options, diceware_repository).words.len();
+        let words_needed = match options.template {
+            Some(ref template) => template.word_count(),
+            None => options.password_length_max.unwrap_or(0).max(options.password_length),
+        };
+        if list_size < words_needed {
+            return Err(Message::new("error.distinct_words", &[&list_size, &words_needed]));
+        }
+    }
+    if options.unique_passwords
+        && calculate_strength(options, diceware_repository).bits() < (options.password_count as f64).log2() {
+        return Err(Message::new("error.distinct_passwords", &[&options.password_count]));
+    }
+    Ok(())
+}
+
+/// Checks if password policy and/or maximal length (if specified) could be met using specified options.
+pub fn validate_policy(options: &Options, diceware_repository: &[DicewareInfo]) -> Result<(), Message> {
+    if let Some(mut lengths) = password_length_range(options) {
+        return lengths.try_for_each(|password_length| {
+            validate_policy(&with_password_length(options, password_length), diceware_repository)
+        });
+    }
+    let info = word_list(options, diceware_repository);
+
+    match effective_policy(options) {
+        Some(ref policy) => policy.plan(options, &info.words).map(|_plan| ()),
+        None => Ok(()),
+    }
+}
+
+/// Words that are not used for generating passwords because passwords built of them could be ambiguous.
+///
+/// If words are not separated (empty separator or adjacent words in template), and capitalization does not mark
+/// the beginning of every word - passwords built of words being prefixes of other words could be split into words
+/// in more than one way (i.e. "no" + "table" vs "notable"), so their real entropy would be lower than reported.
+/// Such words are excluded from the list, and the entropy is calculated using the reduced list.
+pub fn excluded_ambiguous_words(options: &Options, diceware_repository: &[DicewareInfo]) -> Vec<String> {
+    let info = get_diceware_info_by_language(&options.language, diceware_repository);
+    let words_adjacent = match options.template {
+        Some(ref template) => template.has_adjacent_words(),
+        None => options.separator.is_empty() && options.separator_set.is_none() && options.password_length > 1,
+    };
+    let boundaries_marked = matches!(options.capitalization, Capitalization::Title | Capitalization::Camel);
+
+    match words_adjacent && !boundaries_marked {
+        true => ::diceware_info::find_prefix_words(&info.words),
+        false => Vec::new(),
+    }
+}
+
+fn exclude_ambiguous_words(options: &Options, mut info: DicewareInfo) -> DicewareInfo {
+    let excluded = excluded_ambiguous_words(options, ::std::slice::from_ref(&info))
+        .into_iter()
+        .collect::<HashSet<String>>();
+    if !excluded.is_empty() {
+        info.words.retain(|word| !excluded.contains(word));
+    }
+    info
+}
+
+/// Word list passwords are generated from: ambiguous words are excluded, and (if words should not repeat)
+/// duplicates are removed, so that every word is equally likely to be chosen.
+fn word_list(options: &Options, diceware_repository: &[DicewareInfo]) -> DicewareInfo {
+    let mut info = exclude_ambiguous_words(options, get_diceware_info_by_language(&options.language, diceware_repository));
+    if options.unique_words {
+        let mut seen = HashSet::new();
+        info.words.retain(|word| seen.insert(word.clone()));
+    }
+    info
+}
+
+/// Range of password lengths (in words), if length of every password should be chosen randomly.
+fn password_length_range(options: &Options) -> Option<::std::ops::RangeInclusive<usize>> {
+    match options.password_length_max {
+        Some(max) if max > options.password_length => Some(options.password_length..=max),
+        _ => None,
+    }
+}
+
+fn with_password_length(options: &Options, password_length: usize) -> Options {
+    let mut options = options.clone();
+    options.password_length = password_length;
+    options.password_length_max = None;
+    options
+}
+
+/// Password policy with maximal length of password (`max_characters`) taken into account.
+/// Maximal length is enforced exactly as policy's length limit: passwords are regenerated until they fit,
+/// and entropy is reduced by the fraction of passwords that fit.
+fn effective_policy(options: &Options) -> Option<Policy> {
+    match options.max_characters {
+        None => options.policy.clone(),
+        Some(max_characters) => {
+            let mut policy = options.policy.clone().unwrap_or_default();
+            policy.max_length = Some(policy.max_length.map_or(max_characters, |max| max.min(max_characters)));
+            Some(policy)
+        }
+    }
+}
+
+/// Finds the minimal number of words a password has to consist of to reach entropy of at least `bits`.
+/// All the other options (selected word list etc.) are taken into account, `options.password_length` is ignored.
+///
+/// Returns None if the target could not be reached with the maximal allowed number of words.
+pub fn password_length_for_entropy(
+    options: &Options,
+    diceware_repository: &[DicewareInfo],
+    bits: f64,
+) -> Option<usize> {
+    let mut candidate = options.clone();
+    (::option_parser::MIN_WORDS_COUNT..=::option_parser::MAX_WORDS_COUNT).find(|&password_length| {
+        candidate.password_length = password_length;
+        calculate_strength(&candidate, diceware_repository).bits() >= bits
+    })
 }
 
 /// Alternative for generate_diceware_passwords
@@ -60,32 +357,34 @@ pub fn generate_diceware_passwords(
 ///        6,          // 6 words per password
 ///        3,          // generate 3 passwords
 ///        "-",        // separate words by dash ('-')
-///        false);     // do not simulate dice rolls
+///        false)      // do not simulate dice rolls
+///        .expect("passwords could not be generated using the options given");
 /// ```
 pub fn generate_diceware_passwords_simple(language: &str,
                                password_length: usize,
                                passwords_count: usize,
                                separator: &str,
-                               simulate_dices: bool) -> String {
+                               simulate_dices: bool) -> Result<Vec<Password>, Message> {
     let repository = ::diceware_info::build_diceware_repository();
     let options = ::option_parser::Options {
-        language : language.to_string(),
-        separator : separator.to_string(),
+        language: language.to_string(),
+        separator: separator.to_string(),
         password_length,
-        password_count : passwords_count,
+        password_count: passwords_count,
         simulate_dices,
-        clipboard : false,
-        help : false,
+        ..Default::default()
     };
 
-    let passwords = generate_all_passwords(&options, repository);
-    passwords
+    // clipboard is not used, so it works exactly as generate_diceware_passwords
+    generate_diceware_passwords(&options, repository)
 }
 
 
 pub struct PasswordsIterator {
     repository: Vec<DicewareInfo>,
     options: ::option_parser::Options,
+    /// prepared when the first password is generated (options could be changed until then)
+    prepared: Option<PreparedOptions>,
 }
 
 impl PasswordsIterator {
@@ -99,22 +398,30 @@ impl PasswordsIterator {
                 language: language.to_string(),
                 separator: separator.to_string(),
                 password_length,
-                password_count: 1,
                 simulate_dices,
-                clipboard: false,
-                help: false,
-            }
+                ..Default::default()
+            },
+            prepared: None,
         }
     }
+
+    /// Sets capitalization style applied to words of all passwords generated by the iterator.
+    pub fn with_capitalization(mut self, capitalization: Capitalization) -> PasswordsIterator {
+        self.options.capitalization = capitalization;
+        self.prepared = None;
+        self
+    }
 }
 
 impl Iterator for PasswordsIterator {
-    type Item = String;
+    type Item = Password;
 
     fn next(&mut self) -> Option<Self::Item> {
-
-        let password = generate_single_password(&self.options, &self.repository);
-        Some(password.clone())
+        let options = &self.options;
+        let repository = &self.repository;
+        let prepared = self.prepared.get_or_insert_with(|| PreparedOptions::new(options, repository));
+        // passwords of the iterator could not fail, extra character is never inserted
+        prepared.generate().ok()
     }
 }
 
@@ -123,65 +430,322 @@ fn get_diceware_info_by_language(
     language: &str,
     diceware_repository: &[DicewareInfo],
 ) -> DicewareInfo {
+    find_diceware_info(language, diceware_repository).clone()
+}
+
+fn find_diceware_info<'a>(
+    language: &str,
+    diceware_repository: &'a [DicewareInfo],
+) -> &'a DicewareInfo {
     match language.to_lowercase().as_str() {
         "pl" => diceware_repository
             .iter()
             .find(|di| di.language.as_str() == "pl")
-            .expect("unable to find data for language [pl]")
-            .clone(),
+            .expect("unable to find data for language [pl]"),
         _ => diceware_repository
             .iter()
             .find(|di| di.language.as_str() == "en")
-            .expect("unable to find data for default language [en]")
-            .clone(),
+            .expect("unable to find data for default language [en]"),
     }
 }
 
-fn get_random_word(language: &str, diceware_repository: &[DicewareInfo], simulate_dices: bool) -> String {
-    let info: DicewareInfo = get_diceware_info_by_language(language, diceware_repository.as_ref());
-
-    //#[cfg(debug_assertions)]
-    //println!("number of dice rolls: {:?}", info.num_dices);
-
+fn random_word_index(list_size: usize, num_dices: u8, simulate_dices: bool) -> usize {
     let result = match simulate_dices {
-        true => super::dices::roll_dices(info.num_dices),
-        false => super::dices::get_random_number(info.words.len() as u32)
+        true => roll_word_index(list_size, num_dices),
+        false => super::dices::get_random_number(list_size as u32)
     };
 
     //#[cfg(debug_assertions)] {
     //println!("index: {:?}", result);
-    //println!(
-    //    "selected word: {}",
-    //    info.words[result as usize % info.words.len()]
-    //);
     //}
 
-    info.words[result as usize % info.words.len()].clone()
+    result as usize % list_size
+}
+
+/// Rolls dices until the result could be mapped onto the list uniformly.
+/// For complete lists rolls are never repeated, but lists restricted (i.e. by password policy) may be shorter.
+fn roll_word_index(list_size: usize, num_dices: u8) -> u32 {
+    let outcomes = 6u32.pow(num_dices as u32);
+    let usable_outcomes = outcomes - outcomes % list_size as u32;
+    loop {
+        let result = super::dices::roll_dices(num_dices);
+        if result < usable_outcomes {
+            return result;
+        }
+    }
+}
+
+/// Everything derived from options that does not change between passwords (word lists restricted by the options,
+/// strength, etc.), so it is worked out once for all the passwords generated using the same options.
+#[rustfmt::skip]
+pub(crate) struct PreparedOptions {
+    /// one for every possible length of password
+    lengths:    Vec<PreparedLength>,
+    /// position of every word on the complete list
+    indices:    HashMap<String, usize>,
+    list_id:    String,
+}
+
+/// Options of passwords of a single length, along with the word list and policy plan such passwords are generated using.
+#[rustfmt::skip]
+struct PreparedLength {
+    options:    Options,
+    info:       DicewareInfo,
+    policy:     Option<(Policy, PolicyPlan)>,
+    strength:   PasswordStrength,
+}
+
+impl PreparedOptions {
+    pub(crate) fn new(options: &Options, diceware_repository: &[DicewareInfo]) -> PreparedOptions {
+        let lengths = match password_length_range(options) {
+            Some(lengths) => lengths.map(|password_length| with_password_length(options, password_length)).collect(),
+            None => vec![options.clone()],
+        };
+        let list = find_diceware_info(&options.language, diceware_repository);
+        let mut indices = HashMap::with_capacity(list.words.len());
+        for (index, word) in list.words.iter().enumerate() {
+            indices.entry(word.clone()).or_insert(index);
+        }
+
+        PreparedOptions {
+            lengths: lengths.into_iter().map(|options| PreparedLength::new(options, diceware_repository)).collect(),
+            indices,
+            list_id: list.language.clone(),
+        }
+    }
+
+    /// Fails only if physical dice rolls given run out (see `compose_password`).
+    pub(crate) fn generate(&self) -> Result<Password, Message> {
+        self.generate_length(self.choose_length())
+    }
+
+    /// Appends a password to `password`, taking words directly from the list - so nothing is allocated
+    /// (once `indices` and `password` have grown big enough). Words are chosen and joined by `push_password` - as by `generate`,
+    /// so given the same random numbers both give the same passwords. Passwords having to be checked or modified after they have been built
+    /// (policy, maximal length, template, extra character) are generated by `generate` and copied.
+    pub(crate) fn generate_into(&self, indices: &mut Vec<usize>, password: &mut String) -> Result<(), Message> {
+        let prepared = self.choose_length();
+        let options = &prepared.options;
+        if options.template.is_some() || prepared.policy.is_some() || options.extra_security != ExtraSecurity::Disabled {
+            password.push_str(self.generate_length(prepared)?.as_str());
+            return Ok(());
+        }
+        let info = &prepared.info;
+        push_password(options, &info.words, info.num_dices, options.capitalization, indices, password, None);
+        Ok(())
+    }
+
+    fn choose_length(&self) -> &PreparedLength {
+        match self.lengths.len() {
+            1 => &self.lengths[0],
+            choices => &self.lengths[super::dices::get_random_number(choices as u32) as usize],
+        }
+    }
+
+    fn generate_length(&self, prepared: &PreparedLength) -> Result<Password, Message> {
+        let options = &prepared.options;
+        let info = &prepared.info;
+        let mut password = match options.template {
+            Some(ref template) => generate_from_template(template, options, info),
+            None => match prepared.policy {
+                None => compose_password(options, &info.words, info.num_dices, options.capitalization)?,
+                Some((ref policy, ref plan)) => loop {
+                    let mut password = compose_password(options, &plan.words, info.num_dices, plan.capitalization)?;
+                    plan.decorate(&mut password.rendered);
+                    if policy.is_satisfied_by(&password.rendered) {
+                        break password;
+                    }
+                },
+            },
+        };
+
+        // indices refer to the complete list, not to the one restricted by options
+        password.indices = password.words.iter()
+            .map(|word| *self.indices.get(word).expect("word not found in the word list"))
+            .collect();
+        password.list_id = self.list_id.clone();
+        password.entropy = prepared.strength;
+        Ok(password)
+    }
 }
 
-fn generate_single_password(options: &Options, diceware_repository: &[DicewareInfo]) -> String {
-    let language = &options.language[..];
+impl PreparedLength {
+    fn new(options: Options, diceware_repository: &[DicewareInfo]) -> PreparedLength {
+        let info = word_list(&options, diceware_repository);
+        let policy = match options.template {
+            Some(_) => None,
+            None => effective_policy(&options).map(|policy| {
+                let plan = policy.plan(&options, &info.words)
+                    .expect("password policy could not be met");
+                (policy, plan)
+            }),
+        };
+        let strength = calculate_strength(&options, diceware_repository);
 
-    let mut words: Vec<String> = Vec::with_capacity(options.password_length);
-    for _i in { 0..options.password_length } {
-        let word = get_random_word(language, diceware_repository, options.simulate_dices);
-        words.push(word);
+        PreparedLength { options, info, policy, strength }
     }
+}
 
-    words.join(&options.separator)
+/// Password consisting of given words, everything but the words and the password itself is filled in later.
+fn new_password(words: Vec<String>, separator: Option<String>, separators: Vec<char>, rendered: String) -> Password {
+    Password {
+        words,
+        indices: Vec::new(),
+        separator,
+        separators,
+        list_id: String::new(),
+        entropy: PasswordStrength { words: 0.0, extra: 0.0 },
+        rendered,
+    }
 }
 
-fn generate_all_passwords(options: &Options, diceware_repository: Vec<DicewareInfo>) -> String {
-    let mut all_passwords: Vec<String> = Vec::<String>::with_capacity(options.password_count);
-    for _i in { 0..options.password_count } {
-        let password = generate_single_password(&options, &diceware_repository);
-        all_passwords.push(password);
+/// Template defines the whole structure of password, so only word list, dice simulation and capitalization options are taken into account.
+fn generate_from_template(template: &Template, options: &Options, info: &DicewareInfo) -> Password {
+    let mut indices = Vec::with_capacity(template.word_count());
+    let choice = choose_words(options, &info.words, info.num_dices, template.word_count(), options.capitalization, &mut indices);
+    let capitalized = capitalized_words(&info.words, &indices, options.capitalization, choice);
+
+    let rendered = template.render(&capitalized);
+    new_password(chosen_words(&info.words, &indices), None, Vec::new(), rendered)
+}
+
+/// Chooses a random word (its index in `list`), if words should not repeat - words already `chosen` are rejected
+/// and the choice is repeated (which is equivalent to choosing without replacement).
+fn choose_word(options: &Options, list: &[String], num_dices: u8, chosen: &[usize]) -> usize {
+    loop {
+        let index = random_word_index(list.len(), num_dices, options.simulate_dices);
+        if !options.unique_words || !chosen.iter().any(|&other| list[other] == list[index]) {
+            return index;
+        }
+    }
+}
+
+/// Chooses `count` words (their indices in `list` replace the content of `indices`) and then randomized capitalization of them.
+fn choose_words(
+    options: &Options,
+    list: &[String],
+    num_dices: u8,
+    count: usize,
+    capitalization: Capitalization,
+    indices: &mut Vec<usize>,
+) -> Choice {
+    indices.clear();
+    for _i in 0..count {
+        let index = choose_word(options, list, num_dices, indices);
+        indices.push(index);
+    }
+    capitalization.choose(indices.len(), |position| list[indices[position]].as_str())
+}
+
+fn chosen_words(list: &[String], indices: &[usize]) -> Vec<String> {
+    indices.iter().map(|&index| list[index].clone()).collect()
+}
+
+fn capitalized_words(list: &[String], indices: &[usize], capitalization: Capitalization, choice: Choice) -> Vec<String> {
+    indices.iter().enumerate()
+        .map(|(position, &index)| {
+            let mut word = String::with_capacity(list[index].len());
+            capitalization.push_word(&mut word, &list[index], position, choice);
+            word
+        })
+        .collect()
+}
+
+/// Chooses words of a password and appends them (capitalized and joined, see `join_words`) to `password`.
+/// Words are copied straight from the list, so nothing is allocated once `indices` and `password` have grown big enough.
+fn push_password(
+    options: &Options,
+    list: &[String],
+    num_dices: u8,
+    capitalization: Capitalization,
+    indices: &mut Vec<usize>,
+    password: &mut String,
+    separators: Option<&mut Vec<char>>,
+) {
+    let choice = choose_words(options, list, num_dices, options.password_length, capitalization, indices);
+    join_words(options, indices.len(), password, separators, |password, position| {
+        capitalization.push_word(password, &list[indices[position]], position, choice)
+    });
+}
+
+/// Fails if physical dice rolls run out before extra character is inserted (rolls out of range for the chosen word are repeated).
+fn compose_password(options: &Options, list: &[String], num_dices: u8, capitalization: Capitalization) -> Result<Password, Message> {
+    let mut indices = Vec::with_capacity(options.password_length);
+    let mut rendered = String::new();
+    let mut separators = Vec::new();
+    match options.extra_security {
+        ExtraSecurity::Disabled => {
+            push_password(options, list, num_dices, capitalization, &mut indices, &mut rendered, Some(&mut separators));
+        },
+        // extra character is inserted into capitalized words, so they have to be allocated before they are joined
+        _ => {
+            let choice = choose_words(options, list, num_dices, options.password_length, capitalization, &mut indices);
+            let mut transformed = capitalized_words(list, &indices, capitalization, choice);
+            match options.extra_security {
+                ExtraSecurity::Disabled => {},
+                ExtraSecurity::SimulatedDice => {
+                    ::extra_security::insert_extra_character(&mut transformed, &mut SimulatedDice);
+                },
+                ExtraSecurity::PhysicalDice(ref rolls) => {
+                    ::extra_security::insert_extra_character(&mut transformed, &mut PhysicalDice::new(rolls))
+                        .ok_or_else(|| Message::new("error.dice_rolls_run_out", &[]))?;
+                },
+            }
+            join_words(options, transformed.len(), &mut rendered, Some(&mut separators), |password, position| {
+                password.push_str(&transformed[position])
+            });
+        },
+    }
+
+    let separator = match options.separator_set {
+        None => Some(options.separator.clone()),
+        Some(_) => None,
+    };
+    Ok(new_password(chosen_words(list, &indices), separator, separators, rendered))
+}
+
+/// Appends `count` words (appended by `push_word`, given position of the word) to `password`, joined using separator,
+/// or - if separator set is specified - separators randomly chosen (independently) from the set.
+/// Separators chosen from the set are appended to `separators` (if given).
+fn join_words<F: FnMut(&mut String, usize)>(
+    options: &Options,
+    count: usize,
+    password: &mut String,
+    mut separators: Option<&mut Vec<char>>,
+    mut push_word: F,
+) {
+    for position in 0..count {
+        if position > 0 {
+            match options.separator_set {
+                None => password.push_str(&options.separator),
+                Some(ref separator_set) => {
+                    let separator = separator_set[super::dices::get_random_number(separator_set.len() as u32) as usize];
+                    password.push(separator);
+                    if let Some(ref mut separators) = separators {
+                        separators.push(separator);
+                    }
+                },
+            }
+        }
+        push_word(password, position);
+    }
+}
+
+/// Unlimited number of passwords could not be kept in memory, so no passwords are generated then.
+/// Passwords kept in memory could not fail to be stored, so the error is returned only if passwords could not be generated.
+pub(crate) fn generate_all_passwords(options: &Options, diceware_repository: &[DicewareInfo]) -> io::Result<Vec<Password>> {
+    let mut all_passwords: Vec<Password> = Vec::<Password>::with_capacity(options.password_count);
+    if options.password_count != ::option_parser::UNLIMITED_PASSWORD_COUNT {
+        generate_passwords(options, diceware_repository, |password| {
+            all_passwords.push(password);
+            Ok(())
+        })?;
     }
 
-    all_passwords.join("\n")
+    Ok(all_passwords)
 }
 
-fn copy_to_clipboard(password: String) {
+pub(crate) fn copy_to_clipboard(password: String) {
     let mut ctx: ClipboardContext = ClipboardProvider::new()
         .expect("error accessing clipboard");
     let _clipboard_result = ctx.set_contents(password.to_owned());
@@ -193,6 +757,11 @@ fn copy_to_clipboard(password: String) {
 mod passwords_tests {
     use super::DicewareInfo;
     use super::*;
+    use output::OutputFormat;
+
+    fn generate_single_password(options: &Options, diceware_repository: &[DicewareInfo]) -> Password {
+        PreparedOptions::new(options, diceware_repository).generate().unwrap()
+    }
 
     fn build_fake_diceware_repository() -> Vec<DicewareInfo> {
         vec![
@@ -248,8 +817,10 @@ mod passwords_tests {
     fn generate_single_word_should_return_one_word_in_specified_language() {
         let diceware_repository = build_fake_diceware_repository();
 
-        let word = get_random_word("pl", &diceware_repository, false);
-        assert!(word.len() > 0);
+        let info = get_diceware_info_by_language("pl", &diceware_repository);
+
+        let word = &info.words[random_word_index(info.words.len(), info.num_dices, false)];
+        assert!(!word.is_empty());
         assert!(word.starts_with("pl"));
     }
 
@@ -257,37 +828,32 @@ mod passwords_tests {
     fn generate_single_password_should_return_password_consisting_of_specified_number_of_words() {
         let diceware_repository = build_fake_diceware_repository();
 
-        let options = Options {
-            language: s!("pl"),
-            clipboard: false,
-            help: false,
-            password_count: 1,
-            password_length: 2,
-            separator: s!(" "),
-            simulate_dices: false,
-        };
-        let password = generate_single_password(&options, &diceware_repository);
+        let options = Options { language: s!("pl"), password_length: 2, separator: s!(" "), ..Default::default() };
+        let password = generate_single_password(&options, &diceware_repository).rendered;
         let words_count = password.split_whitespace().count();
         assert_eq!(words_count, options.password_length);
     }
 
     #[test]
-    fn dash_is_used_as_default_separator() {
+    fn empty_separator_joins_words_directly() {
         let diceware_repository = build_fake_diceware_repository();
 
         let password_length: usize = 4;
-        let options = Options {
-            language: s!("pl"),
-            clipboard: false,
-            help: false,
-            password_count: 1,
-            password_length,
-            separator: s!(""),
-            simulate_dices: false,
-        };
+        let options = Options { language: s!("pl"), password_length, separator: s!(""), ..Default::default() };
 
         let password = generate_single_password(&options, &diceware_repository);
-        assert!(password.contains("-"));
+        assert_eq!(password.rendered, password.words.concat());
+    }
+
+    #[test]
+    fn dash_is_used_as_default_separator() {
+        let diceware_repository = build_fake_diceware_repository();
+
+        let args = vec![s!("./dpg"), s!("-l:pl"), s!("-w:4")];
+        let options = ::option_parser::parse_command_line(args).unwrap();
+
+        let password = generate_single_password(&options, &diceware_repository);
+        assert_eq!(password.rendered, password.words.join("-"));
     }
 
     #[test]
@@ -296,17 +862,9 @@ mod passwords_tests {
 
         let password_length: usize = 4;
         let separator = s!("*");
-        let options = Options {
-            language: s!("pl"),
-            clipboard: false,
-            help: false,
-            password_count: 1,
-            password_length,
-            separator,
-            simulate_dices: false,
-        };
+        let options = Options { language: s!("pl"), password_length, separator, ..Default::default() };
 
-        let password = generate_single_password(&options, &diceware_repository);
+        let password = generate_single_password(&options, &diceware_repository).rendered;
         assert!(password.contains(&options.separator));
     }
 
@@ -316,19 +874,371 @@ mod passwords_tests {
         let expected_passwords_count: usize = 5;
         let options = Options {
             language: s!("pl"),
-            clipboard: false,
-            help: false,
             password_count: expected_passwords_count,
             password_length: 1,
             separator: s!(""),
-            simulate_dices: false,
+            ..Default::default()
         };
 
-        let password = generate_all_passwords(&options, diceware_repository);
-        let passwords_count = password.lines().count();
+        let password = generate_all_passwords(&options, &diceware_repository).unwrap();
+        let passwords_count = password.len();
         assert_eq!(passwords_count, expected_passwords_count);
     }
 
+    #[test]
+    fn strength_depends_on_list_size_and_password_length() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options { password_length: 3, ..Default::default() };
+
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert_eq!(strength.bits(), 3.0 * 6f64.log2());
+    }
+
+    #[test]
+    fn password_length_for_entropy_returns_minimal_number_of_words() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options { password_length: 1, ..Default::default() };
+
+        // each word from 6 word list adds ~2.58 bits
+        assert_eq!(password_length_for_entropy(&options, &diceware_repository, 2.0), Some(1));
+        assert_eq!(password_length_for_entropy(&options, &diceware_repository, 10.0), Some(4));
+        assert_eq!(password_length_for_entropy(&options, &diceware_repository, 1000.0), None);
+    }
+
+    #[test]
+    fn capitalization_style_is_applied_to_generated_password() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            language: s!("pl"),
+            password_length: 3,
+            separator: s!(" "),
+            capitalization: Capitalization::Upper,
+            ..Default::default()
+        };
+
+        let password = generate_single_password(&options, &diceware_repository).rendered;
+        assert!(password.split_whitespace().all(|w| w.starts_with("PL-")));
+    }
+
+    #[test]
+    fn randomized_capitalization_adds_entropy() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options { password_length: 4, capitalization: Capitalization::RandomWord, ..Default::default() };
+
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert_eq!(strength.extra, 2.0);
+    }
+
+    #[test]
+    fn extra_character_is_inserted_into_generated_password() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            password_length: 2,
+            separator: s!(" "),
+            extra_security: ExtraSecurity::PhysicalDice(vec![1, 1, 6, 6]),
+            ..Default::default()
+        };
+
+        let password = generate_single_password(&options, &diceware_repository).rendered;
+        assert!(password.starts_with("9en-"));
+    }
+
+    #[test]
+    fn running_out_of_physical_dice_rolls_fails_generation() {
+        let diceware_repository = build_fake_diceware_repository();
+        let mut options = ::option_parser::parse_command_line(vec![s!("./dpg"), s!("-w:2")]).unwrap();
+        // 6th letter is out of range for every word of the list, so the only roll left chooses the row
+        options.extra_security = ExtraSecurity::PhysicalDice(vec![1, 6, 1, 1]);
+
+        let error = PreparedOptions::new(&options, &diceware_repository).generate().unwrap_err();
+        assert_eq!(error.key(), "error.dice_rolls_run_out");
+        let error = write_passwords(&options, &diceware_repository, Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn generated_password_complies_with_policy() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            password_length: 2,
+            separator: s!(" "),
+            simulate_dices: true,
+            policy: Some("upper,digit,symbol,forbid=5".parse::<Policy>().unwrap()),
+            ..Default::default()
+        };
+
+        for _i in 0..100 {
+            let password = generate_single_password(&options, &diceware_repository).rendered;
+            assert!(options.policy.as_ref().unwrap().is_satisfied_by(&password));
+            assert!(!password.contains("En-5"));
+        }
+    }
+
+    #[test]
+    fn policy_is_reflected_in_strength() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            password_length: 2,
+            separator: s!(" "),
+            policy: Some("digit,forbid=56".parse::<Policy>().unwrap()),
+            ..Default::default()
+        };
+
+        // 4 out of 6 words are allowed, one of 8 allowed digits is appended
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert_eq!(strength.words, 4.0);
+        assert_eq!(strength.extra, 3.0);
+    }
+
+    #[test]
+    fn generated_passwords_do_not_exceed_max_characters() {
+        let diceware_repository = vec![DicewareInfo {
+            language: s!("en"),
+            num_dices: 1,
+            words: vec![s!("a"), s!("bb"), s!("ccc"), s!("dddd"), s!("eeeee"), s!("ffffff")],
+        }];
+        let options = Options {
+            password_length: 2,
+            simulate_dices: true,
+            max_characters: Some(5),
+            ..Default::default()
+        };
+
+        for _i in 0..100 {
+            let password = generate_single_password(&options, &diceware_repository).rendered;
+            assert!(password.chars().count() <= 5);
+        }
+        // 6 out of 36 word pairs are at most 4 characters long
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert!((strength.bits() - 6f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn password_is_generated_according_to_template() {
+        let diceware_repository = build_fake_diceware_repository();
+        let template = "W.W.D2".parse::<Template>().unwrap();
+        let options = Options {
+            language: s!("pl"),
+            password_length: template.word_count(),
+            template: Some(template),
+            ..Default::default()
+        };
+
+        let password = generate_single_password(&options, &diceware_repository).rendered;
+        let parts = password.split('.').collect::<Vec<&str>>();
+        assert_eq!(parts.len(), 3);
+        assert!(parts[0].starts_with("pl-") && parts[1].starts_with("pl-"));
+        assert!(parts[2].len() == 2 && parts[2].chars().all(|c| c.is_ascii_digit()));
+
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert!((strength.bits() - (2.0 * 6f64.log2() + 2.0 * 10f64.log2())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn separators_are_chosen_from_separator_set() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            language: s!("pl"),
+            password_length: 5,
+            separator_set: Some(vec!['0', '1', '2', '3']),
+            ..Default::default()
+        };
+
+        let password = generate_single_password(&options, &diceware_repository).rendered;
+        let separators = password.split("pl-").skip(1)
+            .map(|word| word.chars().skip(1).collect::<String>())
+            .collect::<Vec<String>>();
+        assert_eq!(separators.len(), 5);
+        assert!(separators[..4].iter().all(|s| s.len() == 1 && "0123".contains(s.as_str())));
+        assert!(separators[4].is_empty());
+
+        // 4 separators, each adds 2 bits
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert_eq!(strength.extra, 8.0);
+    }
+
+    #[test]
+    fn words_being_prefixes_of_other_words_are_not_used_without_separator() {
+        let diceware_repository = vec![DicewareInfo {
+            language: s!("en"),
+            num_dices: 1,
+            words: vec![s!("no"), s!("table"), s!("notable"), s!("cat")],
+        }];
+        let mut options = Options { password_length: 3, separator: s!(""), ..Default::default() };
+
+        assert_eq!(excluded_ambiguous_words(&options, &diceware_repository), vec![s!("no")]);
+        for _i in 0..50 {
+            let password = generate_single_password(&options, &diceware_repository).rendered;
+            assert!(!password.starts_with("not") || password.starts_with("notable"));
+        }
+        assert!((calculate_strength(&options, &diceware_repository).bits() - 3.0 * 3f64.log2()).abs() < 1e-9);
+
+        options.capitalization = Capitalization::Title;
+        assert!(excluded_ambiguous_words(&options, &diceware_repository).is_empty());
+        options.capitalization = Capitalization::Lower;
+        options.separator = s!("-");
+        assert!(excluded_ambiguous_words(&options, &diceware_repository).is_empty());
+    }
+
+    #[test]
+    fn length_of_passwords_is_chosen_from_range() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            language: s!("pl"),
+            password_length: 2,
+            separator: s!(" "),
+            password_length_max: Some(4),
+            ..Default::default()
+        };
+
+        let mut lengths = HashSet::new();
+        for _i in 0..200 {
+            let password = generate_single_password(&options, &diceware_repository).rendered;
+            lengths.insert(password.split_whitespace().count());
+        }
+        assert_eq!(lengths, [2, 3, 4].iter().cloned().collect::<HashSet<usize>>());
+
+        // the shortest password is the weakest one
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert_eq!(strength.bits(), 2.0 * 6f64.log2());
+    }
+
+    #[test]
+    fn words_do_not_repeat_within_password_if_requested() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options { language: s!("pl"), password_length: 6, unique_words: true, ..Default::default() };
+
+        for _i in 0..20 {
+            let password = generate_single_password(&options, &diceware_repository).rendered;
+            let words = password.split("pl-").skip(1).collect::<HashSet<&str>>();
+            assert_eq!(words.len(), 6);
+        }
+
+        // 6 * 5 * 4 * 3 * 2 * 1 = 720 possible passwords
+        let strength = calculate_strength(&options, &diceware_repository);
+        assert!((strength.bits() - 720f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn passwords_do_not_repeat_within_batch_if_requested() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            language: s!("pl"),
+            password_count: 6,
+            password_length: 1,
+            unique_passwords: true,
+            ..Default::default()
+        };
+
+        let passwords = generate_all_passwords(&options, &diceware_repository).unwrap();
+        let passwords = passwords.iter().map(|password| password.as_str()).collect::<HashSet<&str>>();
+        assert_eq!(passwords.len(), 6);
+        assert!(validate_uniqueness(&options, &diceware_repository).is_ok());
+
+        let options = Options { password_count: 7, ..options };
+        assert!(validate_uniqueness(&options, &diceware_repository).is_err());
+        // repeated passwords would be rejected forever
+        let error = generate_all_passwords(&options, &diceware_repository).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let options = Options { password_count: ::option_parser::UNLIMITED_PASSWORD_COUNT, ..options };
+        assert!(write_passwords(&options, &diceware_repository, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn distinct_words_have_to_be_available_in_list() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options { language: s!("pl"), password_length: 7, unique_words: true, ..Default::default() };
+
+        assert!(validate_uniqueness(&options, &diceware_repository).is_err());
+    }
+
+    #[test]
+    fn generated_password_carries_words_it_has_been_built_of() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            language: s!("pl"),
+            password_length: 3,
+            separator: s!("."),
+            capitalization: Capitalization::Upper,
+            ..Default::default()
+        };
+
+        let password = generate_single_password(&options, &diceware_repository);
+        assert_eq!(password.list_id, "pl");
+        assert_eq!(password.separator, Some(s!(".")));
+        assert_eq!(password.words.len(), 3);
+        for (word, &index) in password.words.iter().zip(password.indices.iter()) {
+            assert_eq!(*word, diceware_repository[0].words[index]);
+        }
+        assert_eq!(password.rendered, password.words.join(".").to_uppercase());
+        assert_eq!(password.entropy, calculate_strength(&options, &diceware_repository));
+    }
+
+    /// Accepts given number of writes, then behaves as a pipe closed by the reader.
+    struct ClosingWriter {
+        writes: usize,
+        capacity: usize,
+    }
+
+    impl Write for ClosingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.writes == self.capacity {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "reader has gone"));
+            }
+            self.writes += 1;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn passwords_are_written_as_they_are_generated() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options {
+            password_count: 3,
+            password_length: 2,
+            format: OutputFormat::Plain,
+            ..Default::default()
+        };
+        let mut out: Vec<u8> = Vec::new();
+        write_passwords(&options, &diceware_repository, &mut out).unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert_eq!(output.lines().count(), 3);
+        assert!(output.lines().all(|line| line.len() == 9 && line.starts_with("en-")));
+
+        let options = Options { password_count: ::option_parser::UNLIMITED_PASSWORD_COUNT, ..options };
+        let mut out = ClosingWriter { writes: 0, capacity: 10 };
+        let error = write_passwords(&options, &diceware_repository, &mut out).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(out.writes, 10);
+    }
+
+    #[test]
+    fn passwords_are_written_in_batches() {
+        let diceware_repository = build_fake_diceware_repository();
+        let options = Options { password_count: 100, format: OutputFormat::Plain, ..Default::default() };
+        let mut out = ClosingWriter { writes: 0, capacity: 1 };
+        write_passwords(&options, &diceware_repository, &mut out).unwrap();
+        assert_eq!(out.writes, 1);
+
+        // pipe closed before passwords are flushed is reported as well
+        let mut out = ClosingWriter { writes: 0, capacity: 0 };
+        let error = write_passwords(&options, &diceware_repository, &mut out).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn iterator_applies_capitalization_style() {
+        let mut iterator = PasswordsIterator::new("en", "-", 3, false)
+            .with_capitalization(Capitalization::Title);
+        let password = iterator.next().unwrap().rendered;
+        assert!(password.split('-').all(|w| w.chars().next().unwrap().is_uppercase()));
+    }
+
     #[test]
     fn passwords_copy_to_clipboard() {
         let initial = s!("initial");
@@ -0,0 +1,380 @@
+use std::str::FromStr;
+
+use capitalization::Capitalization;
+use dices;
+use extra_security::{self, ExtraSecurity};
+use messages::Message;
+use option_parser::Options;
+use strength::{distinct_length_probability, length_probability, word_entropy};
+
+/// Digits that could be appended to a password to satisfy "at least one digit" rule.
+static POLICY_DIGITS: &str = "0123456789";
+/// Symbols that could be appended to a password to satisfy "at least one symbol" rule.
+pub static POLICY_SYMBOLS: &str = "!#$%&*+-=?@^_~";
+/// Passwords not fitting length limits are regenerated, so at least that fraction of them has to fit - otherwise generating would be too slow.
+static MIN_LENGTH_PROBABILITY: f64 = 1e-6;
+
+/// Password policy - set of rules (usually imposed by a website) generated password has to comply with.
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Policy {
+    /// minimal length of password (in characters)
+    pub min_length:             usize,
+    /// maximal length of password (in characters)
+    pub max_length:             Option<usize>,
+    pub require_uppercase:      bool,
+    pub require_lowercase:      bool,
+    pub require_digit:          bool,
+    pub require_symbol:         bool,
+    /// characters password must not contain
+    pub forbidden_characters:   String,
+    /// maximal number of identical characters in a row
+    pub max_repeated:           Option<usize>,
+}
+
+/// Parses policy from comma separated list of rules, i.e. "min=8,max=20,digit,symbol,nospace".
+///
+/// Supported rules:
+/// - `min=<n>`, `max=<n>` - minimal/maximal length of password (in characters)
+/// - `upper`, `lower`, `digit`, `symbol` - at least one character of given class is required
+/// - `forbid=<characters>` - characters password must not contain
+/// - `nospace` - password must not contain spaces
+/// - `repeat=<n>` - at most n identical characters in a row
+impl FromStr for Policy {
+    type Err = Message;
+
+    fn from_str(rules: &str) -> Result<Policy, Message> {
+        let mut policy = Policy::default();
+        for rule in rules.split(',').filter(|rule| !rule.is_empty()) {
+            let (name, value) = match rule.find('=') {
+                Some(index) => (&rule[..index], &rule[index + 1..]),
+                None => (rule, ""),
+            };
+            match name.to_lowercase().as_str() {
+                "min" => policy.min_length = parse_number(name, value)?,
+                "max" => policy.max_length = Some(parse_number(name, value)?),
+                "upper" => policy.require_uppercase = true,
+                "lower" => policy.require_lowercase = true,
+                "digit" => policy.require_digit = true,
+                "symbol" => policy.require_symbol = true,
+                "forbid" => policy.forbidden_characters.push_str(value),
+                "nospace" => policy.forbidden_characters.push(' '),
+                "repeat" => policy.max_repeated = Some(parse_number(name, value)?),
+                _ => return Err(Message::new("error.policy.unknown_rule", &[&rule])),
+            }
+        }
+        Ok(policy)
+    }
+}
+
+fn parse_number(rule: &str, value: &str) -> Result<usize, Message> {
+    value.parse::<usize>()
+        .map_err(|_| Message::new("error.policy.number", &[&rule, &value]))
+}
+
+/// Describes how password generation has to be adapted so that generated passwords comply with the policy.
+#[rustfmt::skip]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyPlan {
+    /// words from the list that do not break the policy
+    pub words:              Vec<String>,
+    /// capitalization style, changed to `Title` if the policy requires an uppercase letter and selected style does not guarantee one
+    pub capitalization:     Capitalization,
+    /// digits to choose a digit appended to the password from (empty if there is no need to append a digit)
+    pub digits:             Vec<char>,
+    /// symbols to choose a symbol appended to the password from (empty if there is no need to append a symbol)
+    pub symbols:            Vec<char>,
+    /// fraction of passwords (built of allowed words) that fit length limits
+    pub length_probability: f64,
+}
+
+impl PolicyPlan {
+    /// Appends digit and/or symbol required by the policy.
+    pub fn decorate(&self, password: &mut String) {
+        if !self.digits.is_empty() {
+            password.push(self.digits[dices::get_random_number(self.digits.len() as u32) as usize]);
+        }
+        if !self.symbols.is_empty() {
+            password.push(self.symbols[dices::get_random_number(self.symbols.len() as u32) as usize]);
+        }
+    }
+
+    /// Entropy (in bits) added by appended digit and/or symbol.
+    pub fn decoration_entropy(&self) -> f64 {
+        [&self.digits, &self.symbols].iter()
+            .filter(|characters| !characters.is_empty())
+            .map(|characters| word_entropy(characters.len()))
+            .sum()
+    }
+}
+
+impl Policy {
+    /// Works out how passwords have to be generated (using given options and word list) to comply with the policy.
+    /// Returns an error describing the problem if the policy could not be met.
+    pub fn plan(&self, options: &Options, list: &[String]) -> Result<PolicyPlan, Message> {
+        // every separator that could possibly be used has to comply with the policy
+        let separators: Vec<String> = match options.separator_set {
+            Some(ref set) => set.iter().map(|c| c.to_string()).collect(),
+            None => vec![options.separator.clone()],
+        };
+        for separator in &separators {
+            if separator.chars().any(|c| self.is_forbidden(c)) {
+                return Err(Message::new("error.policy.separator", &[separator]));
+            }
+        }
+        if options.extra_security != ExtraSecurity::Disabled
+            && extra_security::table_contains_any(&self.forbidden_characters) {
+            return Err(Message::new("error.policy.extra", &[]));
+        }
+        if let Some(max_repeated) = self.max_repeated {
+            if max_repeated == 0 {
+                return Err(Message::new("error.policy.repeat", &[]));
+            }
+            for separator in &separators {
+                if separator.is_empty() || separator.chars().any(|c| c.is_alphabetic()) || max_run(separator) > max_repeated {
+                    return Err(Message::new("error.policy.separator_repeats", &[separator]));
+                }
+            }
+        }
+
+        let words: Vec<String> = list.iter()
+            .filter(|word| !word.chars().any(|c| self.is_forbidden(c)))
+            .filter(|word| self.max_repeated.is_none_or(|max| max_run(word) <= max))
+            .cloned()
+            .collect();
+        if words.is_empty() {
+            return Err(Message::new("error.policy.no_words", &[]));
+        }
+        if options.unique_words && words.len() < options.password_length {
+            return Err(Message::new("error.policy.distinct_words", &[&words.len(), &options.password_length]));
+        }
+
+        let capitalization = self.plan_capitalization(options.capitalization, options.password_length)?;
+        // separator appears in the password only if it consists of more than one word
+        let separator_used = options.password_length > 1;
+        let separators_contain = |class: fn(char) -> bool| {
+            separator_used && separators.iter().all(|separator| separator.chars().any(class))
+        };
+        let digits = match self.require_digit && !separators_contain(|c| c.is_ascii_digit()) {
+            true => self.allowed_characters(POLICY_DIGITS, "error.policy.no_digits")?,
+            false => Vec::new(),
+        };
+        let symbols = match self.require_symbol && !separators_contain(is_symbol) {
+            true => self.allowed_characters(POLICY_SYMBOLS, "error.policy.no_symbols")?,
+            false => Vec::new(),
+        };
+
+        let separator_length = separators.iter().map(|separator| separator.chars().count()).max().unwrap_or(0);
+        let fixed_length = separator_length * options.password_length.saturating_sub(1)
+            + (options.extra_security != ExtraSecurity::Disabled) as usize
+            + (!digits.is_empty()) as usize
+            + (!symbols.is_empty()) as usize;
+        let max_words_length = match self.max_length {
+            Some(max_length) if max_length < fixed_length => {
+                return Err(Message::new("error.policy.max_length", &[&max_length]))
+            }
+            Some(max_length) => max_length - fixed_length,
+            None => usize::MAX,
+        };
+        let lengths = words.iter().map(|w| w.chars().count()).collect::<Vec<usize>>();
+        let min_words_length = self.min_length.saturating_sub(fixed_length);
+        let length_probability = match options.unique_words {
+            true => distinct_length_probability(&lengths, options.password_length, min_words_length, max_words_length),
+            false => length_probability(&lengths, options.password_length, min_words_length, max_words_length),
+        };
+        if length_probability == 0.0 {
+            return Err(Message::new("error.policy.length", &[&options.password_length]));
+        }
+        if length_probability < MIN_LENGTH_PROBABILITY {
+            return Err(Message::new("error.policy.restrictive", &[]));
+        }
+
+        Ok(PolicyPlan { words, capitalization, digits, symbols, length_probability })
+    }
+
+    /// Checks if the password complies with the policy.
+    pub fn is_satisfied_by(&self, password: &str) -> bool {
+        let length = password.chars().count();
+
+        length >= self.min_length
+            && self.max_length.is_none_or(|max| length <= max)
+            && (!self.require_uppercase || password.chars().any(|c| c.is_uppercase()))
+            && (!self.require_lowercase || password.chars().any(|c| c.is_lowercase()))
+            && (!self.require_digit || password.chars().any(|c| c.is_ascii_digit()))
+            && (!self.require_symbol || password.chars().any(is_symbol))
+            && !password.chars().any(|c| self.forbidden_characters.contains(c))
+            && self.max_repeated.is_none_or(|max| max_run(password) <= max)
+    }
+
+    /// Forbidden characters are compared case insensitively, as capitalization may change case of letters.
+    fn is_forbidden(&self, c: char) -> bool {
+        self.forbidden_characters.chars()
+            .any(|forbidden| forbidden.to_lowercase().eq(c.to_lowercase()))
+    }
+
+    fn plan_capitalization(&self, capitalization: Capitalization, password_length: usize) -> Result<Capitalization, Message> {
+        let has_uppercase = match capitalization {
+            Capitalization::Lower => false,
+            Capitalization::Camel => password_length > 1,
+            _ => true,
+        };
+        let has_lowercase = match capitalization {
+            Capitalization::Upper => false,
+            Capitalization::RandomWord => password_length > 1,
+            _ => true,
+        };
+        if self.require_lowercase && !has_lowercase {
+            return Err(Message::new("error.policy.no_lowercase", &[&format!("{:?}", capitalization)]));
+        }
+
+        match self.require_uppercase && !has_uppercase {
+            true => Ok(Capitalization::Title),
+            false => Ok(capitalization),
+        }
+    }
+
+    /// Characters (digits or symbols) that are not forbidden, `forbidden_all` is the key of the error if there are none.
+    fn allowed_characters(&self, characters: &str, forbidden_all: &'static str) -> Result<Vec<char>, Message> {
+        let allowed = characters.chars()
+            .filter(|c| !self.is_forbidden(*c))
+            .collect::<Vec<char>>();
+        match allowed.is_empty() {
+            true => Err(Message::new(forbidden_all, &[])),
+            false => Ok(allowed),
+        }
+    }
+}
+
+fn is_symbol(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+/// Length of the longest run of identical characters.
+fn max_run(text: &str) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous = None;
+    for c in text.chars() {
+        current = match previous == Some(c) {
+            true => current + 1,
+            false => 1,
+        };
+        longest = longest.max(current);
+        previous = Some(c);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod policy_tests {
+    use super::*;
+
+    fn options(password_length: usize, separator: &str) -> Options {
+        Options { password_length, separator: separator.to_string(), ..Default::default() }
+    }
+
+    fn list() -> Vec<String> {
+        vec![s!("cat"), s!("dog"), s!("horse"), s!("sheep"), s!("space bar")]
+    }
+
+    #[test]
+    fn policy_is_parsed_from_comma_separated_rules() {
+        let policy = "min=8,max=20,digit,symbol,nospace,repeat=2".parse::<Policy>().unwrap();
+        assert_eq!(policy, Policy {
+            min_length: 8,
+            max_length: Some(20),
+            require_digit: true,
+            require_symbol: true,
+            forbidden_characters: s!(" "),
+            max_repeated: Some(2),
+            ..Policy::default()
+        });
+    }
+
+    #[test]
+    fn unknown_rules_are_reported() {
+        assert!("min=8,emoji".parse::<Policy>().is_err());
+        assert!("max=many".parse::<Policy>().is_err());
+    }
+
+    #[test]
+    fn words_containing_forbidden_characters_or_repeats_are_not_used() {
+        let policy = "nospace,repeat=1".parse::<Policy>().unwrap();
+        let plan = policy.plan(&options(3, "-"), &list()).unwrap();
+        assert_eq!(plan.words, vec![s!("cat"), s!("dog"), s!("horse")]);
+    }
+
+    #[test]
+    fn distinct_words_have_to_be_available() {
+        let policy = "nospace,repeat=1".parse::<Policy>().unwrap();
+        let mut options = options(4, "-");
+        options.unique_words = true;
+        assert!(policy.plan(&options, &list()).is_err());
+
+        options.password_length = 3;
+        assert!(policy.plan(&options, &list()).is_ok());
+    }
+
+    #[test]
+    fn missing_character_classes_are_added() {
+        let policy = "upper,digit,symbol".parse::<Policy>().unwrap();
+        let plan = policy.plan(&options(3, " "), &list()).unwrap();
+        assert_eq!(plan.capitalization, Capitalization::Title);
+        assert_eq!(plan.digits.len(), 10);
+        assert_eq!(plan.symbols.len(), POLICY_SYMBOLS.len());
+        assert_eq!(plan.decoration_entropy(), 10f64.log2() + (POLICY_SYMBOLS.len() as f64).log2());
+    }
+
+    #[test]
+    fn separator_could_satisfy_character_class_requirements() {
+        let policy = "digit,symbol".parse::<Policy>().unwrap();
+        let plan = policy.plan(&options(3, "-1"), &list()).unwrap();
+        assert_eq!(plan.decoration_entropy(), 0.0);
+    }
+
+    #[test]
+    fn separator_set_satisfies_requirement_only_if_all_separators_do() {
+        let policy = "digit".parse::<Policy>().unwrap();
+        let mut digits = options(3, "-");
+        digits.separator_set = Some("0123456789".chars().collect());
+        assert!(policy.plan(&digits, &list()).unwrap().digits.is_empty());
+
+        let mut mixed = options(3, "-");
+        mixed.separator_set = Some("1-".chars().collect());
+        assert_eq!(policy.plan(&mixed, &list()).unwrap().digits.len(), 10);
+    }
+
+    #[test]
+    fn length_limits_are_reflected_in_probability() {
+        // 2 words (3 or 5 letters each, "space bar" forbidden) and a dash: 7, 9 or 11 characters
+        let policy = "max=9,nospace".parse::<Policy>().unwrap();
+        let plan = policy.plan(&options(2, "-"), &list()).unwrap();
+        assert_eq!(plan.length_probability, 0.75);
+    }
+
+    #[test]
+    fn policy_that_could_not_be_met_is_reported() {
+        let too_short = "max=5".parse::<Policy>().unwrap();
+        assert!(too_short.plan(&options(3, "-"), &list()).is_err());
+
+        let forbidden_separator = "forbid=-".parse::<Policy>().unwrap();
+        assert!(forbidden_separator.plan(&options(3, "-"), &list()).is_err());
+
+        let no_digits = "digit,forbid=0123456789".parse::<Policy>().unwrap();
+        assert!(no_digits.plan(&options(3, "-"), &list()).is_err());
+
+        let mut upper = options(3, "-");
+        upper.capitalization = Capitalization::Upper;
+        assert!("lower".parse::<Policy>().unwrap().plan(&upper, &list()).is_err());
+    }
+
+    #[test]
+    fn passwords_are_checked_against_all_rules() {
+        let policy = "min=8,max=20,upper,digit,symbol,nospace,repeat=2".parse::<Policy>().unwrap();
+        assert!(policy.is_satisfied_by("Horse-Dog-Cat7!"));
+        assert!(!policy.is_satisfied_by("Horse-Dog-Cat!"));
+        assert!(!policy.is_satisfied_by("Horse Dog-Cat7!"));
+        assert!(!policy.is_satisfied_by("Sheeep-Dog-Cat7!"));
+        assert!(!policy.is_satisfied_by("C7!"));
+    }
+}
@@ -0,0 +1,249 @@
+use messages::UiLanguage;
+
+/// Describes how fast an attacker is able to check password candidates.
+#[rustfmt::skip]
+pub struct AttackerModel {
+    pub name:               &'static str,
+    pub guesses_per_second: f64,
+}
+
+/// Attacker models used for estimating time needed to crack a password.
+/// Guess rates follow the ones commonly used by password strength estimators (i.e. zxcvbn).
+pub static ATTACKER_MODELS: [AttackerModel; 3] = [
+    AttackerModel { name: "online, throttled (100 guesses/hour)", guesses_per_second: 100.0 / 3600.0 },
+    AttackerModel { name: "offline, slow hash (1e4 guesses/s)", guesses_per_second: 1e4 },
+    AttackerModel { name: "offline, fast hash (1e10 guesses/s)", guesses_per_second: 1e10 },
+];
+
+/// Strength of a password expressed as entropy (in bits).
+///
+/// Entropy is split into two parts:
+/// - `words` - entropy coming from words chosen from the list: log2(list size) * number of words
+/// - `extra` - entropy added by everything else (random separators, transformations, etc.)
+#[rustfmt::skip]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PasswordStrength {
+    pub words:  f64,
+    pub extra:  f64,
+}
+
+impl PasswordStrength {
+    /// Strength of a password consisting of `password_length` words randomly chosen from a list of `list_size` words.
+    pub fn from_words(list_size: usize, password_length: usize) -> PasswordStrength {
+        PasswordStrength {
+            words: word_entropy(list_size) * password_length as f64,
+            extra: 0.0,
+        }
+    }
+
+    /// Total entropy in bits.
+    pub fn bits(&self) -> f64 {
+        self.words + self.extra
+    }
+
+    /// Average time (in seconds) needed to guess the password by the attacker - i.e. half of the keyspace has to be searched.
+    pub fn seconds_to_crack(&self, attacker: &AttackerModel) -> f64 {
+        (self.bits() - 1.0).exp2() / attacker.guesses_per_second
+    }
+}
+
+/// Entropy (in bits) of a single word randomly chosen from a list of `list_size` words.
+pub fn word_entropy(list_size: usize) -> f64 {
+    (list_size as f64).log2()
+}
+
+/// Entropy (in bits) of `count` distinct words randomly chosen (without replacement) from a list of `list_size` words:
+/// log2(list_size * (list_size - 1) * ... * (list_size - count + 1)).
+pub fn distinct_words_entropy(list_size: usize, count: usize) -> f64 {
+    (0..count).map(|i| word_entropy(list_size.saturating_sub(i))).sum()
+}
+
+/// Probability that the total length of `count` words, each chosen uniformly from a list of words of given `lengths`, lies within `min..=max`.
+/// Used for calculating entropy of passwords which are regenerated until they fit length constraints:
+/// only that fraction of all the possible passwords could be generated, so log2(probability) has to be added to the entropy.
+pub fn length_probability(lengths: &[usize], count: usize, min: usize, max: usize) -> f64 {
+    let longest = lengths.iter().cloned().max().unwrap_or(0);
+    let max = max.min(longest * count);
+    if lengths.is_empty() || min > max {
+        return 0.0;
+    }
+
+    let mut single: Vec<(usize, f64)> = Vec::new();
+    for &length in lengths {
+        match single.iter_mut().find(|entry| entry.0 == length) {
+            Some(entry) => entry.1 += 1.0,
+            None => single.push((length, 1.0)),
+        }
+    }
+    single.iter_mut().for_each(|entry| entry.1 /= lengths.len() as f64);
+
+    // distribution[n] - probability that words chosen so far are exactly n characters long
+    let mut distribution = vec![0.0; max + 1];
+    distribution[0] = 1.0;
+    for _i in 0..count {
+        let mut next = vec![0.0; max + 1];
+        for (total, p) in distribution.iter().enumerate().filter(|&(_, p)| *p > 0.0) {
+            for &(length, q) in single.iter().filter(|&&(length, _)| total + length <= max) {
+                next[total + length] += p * q;
+            }
+        }
+        distribution = next;
+    }
+
+    distribution[min..].iter().sum()
+}
+
+/// Same as `length_probability`, but for `count` distinct words chosen from the list (without replacement).
+pub fn distinct_length_probability(lengths: &[usize], count: usize, min: usize, max: usize) -> f64 {
+    let shortest = lengths.iter().cloned().min().unwrap_or(0);
+    let longest = lengths.iter().cloned().max().unwrap_or(0);
+    if count > lengths.len() || min > max.min(longest * count) {
+        return 0.0;
+    }
+    if min <= shortest * count && max >= longest * count {
+        return 1.0;
+    }
+    let max = max.min(longest * count);
+
+    // distribution[j][n] - probability that j distinct words chosen from words processed so far are exactly n characters long;
+    // after processing i-th word: p_i(j, n) = (i - j) / i * p_(i-1)(j, n) + j / i * p_(i-1)(j - 1, n - length)
+    let mut distribution = vec![vec![0.0; max + 1]; count + 1];
+    distribution[0][0] = 1.0;
+    for (i, &length) in lengths.iter().enumerate().map(|(i, length)| (i + 1, length)) {
+        for j in (1..=count.min(i)).rev() {
+            for total in (0..=max).rev() {
+                let taken = match total >= length {
+                    true => distribution[j - 1][total - length],
+                    false => 0.0,
+                };
+                distribution[j][total] = ((i - j) as f64 * distribution[j][total] + j as f64 * taken) / i as f64;
+            }
+        }
+    }
+
+    distribution[count][min..].iter().sum()
+}
+
+/// Formats strength report (entropy & estimated crack times) as a human readable text.
+pub fn format_strength(strength: &PasswordStrength) -> String {
+    format_strength_in(strength, UiLanguage::En)
+}
+
+/// Works as `format_strength`, but the report is written in the given language.
+pub fn format_strength_in(strength: &PasswordStrength, language: UiLanguage) -> String {
+    let mut report = language.format("strength", &[&format!("{:.1}", strength.bits())]);
+    for attacker in ATTACKER_MODELS.iter() {
+        report.push_str(&format!(
+            "  {:<40}{}\n",
+            language.attacker_name(attacker),
+            format_duration_in(strength.seconds_to_crack(attacker), language)
+        ));
+    }
+    report
+}
+
+/// Formats duration given in seconds using the largest unit that makes sense.
+pub fn format_duration(seconds: f64) -> String {
+    format_duration_in(seconds, UiLanguage::En)
+}
+
+/// Works as `format_duration`, but the duration is written in the given language.
+pub fn format_duration_in(seconds: f64, language: UiLanguage) -> String {
+    let minute = 60.0;
+    let hour = 60.0 * minute;
+    let day = 24.0 * hour;
+    let year = 365.25 * day;
+
+    let (value, unit) = match seconds {
+        s if s < 1.0 => return s!(language.text("duration.less_than_second")),
+        s if s < minute => (s, "seconds"),
+        s if s < hour => (s / minute, "minutes"),
+        s if s < day => (s / hour, "hours"),
+        s if s < year => (s / day, "days"),
+        s if s < 1e6 * year => (s / year, "years"),
+        s => return language.format("duration.years.many", &[&format!("{:.1e}", s / year)]),
+    };
+    let key = format!("duration.{}.{}", unit, language.plural(value.round() as u64));
+    language.format(&key, &[&format!("{:.0}", value)])
+}
+
+#[cfg(test)]
+mod strength_tests {
+    use super::*;
+
+    #[test]
+    fn entropy_of_a_word_from_6_word_list_is_log2_of_6() {
+        assert_eq!(word_entropy(6), 6f64.log2());
+    }
+
+    #[test]
+    fn entropy_grows_linearly_with_number_of_words() {
+        let strength = PasswordStrength::from_words(7776, 6);
+        assert!((strength.bits() - 77.548).abs() < 0.001);
+    }
+
+    #[test]
+    fn one_bit_password_is_cracked_after_single_guess_on_average() {
+        let strength = PasswordStrength { words: 1.0, extra: 0.0 };
+        let attacker = AttackerModel { name: "test", guesses_per_second: 1.0 };
+        assert_eq!(strength.seconds_to_crack(&attacker), 1.0);
+    }
+
+    #[test]
+    fn extra_entropy_is_included_in_total() {
+        let strength = PasswordStrength { words: 20.0, extra: 3.5 };
+        assert_eq!(strength.bits(), 23.5);
+    }
+
+    #[test]
+    fn length_probability_of_unconstrained_password_is_1() {
+        let lengths = [3, 4, 5, 6];
+        assert!((length_probability(&lengths, 3, 0, usize::MAX) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn length_probability_counts_only_fitting_combinations() {
+        // two words, each 3 or 5 characters long: 6, 8, 8, 10
+        let lengths = [3, 5];
+        assert_eq!(length_probability(&lengths, 2, 0, 8), 0.75);
+        assert_eq!(length_probability(&lengths, 2, 8, 8), 0.5);
+        assert_eq!(length_probability(&lengths, 2, 11, 20), 0.0);
+    }
+
+    #[test]
+    fn entropy_of_distinct_words_is_log2_of_number_of_permutations() {
+        // 6 * 5 * 4 = 120 possible passwords
+        assert!((distinct_words_entropy(6, 3) - 120f64.log2()).abs() < 1e-9);
+        assert!(distinct_words_entropy(6, 3) < PasswordStrength::from_words(6, 3).bits());
+    }
+
+    #[test]
+    fn distinct_length_probability_counts_only_fitting_combinations() {
+        // pairs of distinct words: {3, 5}, {3, 7}, {5, 7} => 8, 10, 12
+        let lengths = [3, 5, 7];
+        assert!((distinct_length_probability(&lengths, 2, 0, 10) - 2.0 / 3.0).abs() < 1e-9);
+        assert!((distinct_length_probability(&lengths, 2, 10, 10) - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(distinct_length_probability(&lengths, 2, 0, 100), 1.0);
+        assert_eq!(distinct_length_probability(&lengths, 4, 0, 100), 0.0);
+    }
+
+    #[test]
+    fn durations_are_formatted_using_largest_unit() {
+        assert_eq!(format_duration(0.5), "less than a second");
+        assert_eq!(format_duration(30.0), "30 seconds");
+        assert_eq!(format_duration(7200.0), "2 hours");
+        assert_eq!(format_duration(3.0 * 86400.0), "3 days");
+        assert_eq!(format_duration(1e20), "3.2e12 years");
+        assert_eq!(format_duration(60.0), "1 minute");
+    }
+
+    #[test]
+    fn durations_are_translated_using_plural_forms() {
+        assert_eq!(format_duration_in(0.5, UiLanguage::Pl), "mniej niż sekunda");
+        assert_eq!(format_duration_in(60.0, UiLanguage::Pl), "1 minuta");
+        assert_eq!(format_duration_in(7200.0, UiLanguage::Pl), "2 godziny");
+        assert_eq!(format_duration_in(5.0 * 86400.0, UiLanguage::Pl), "5 dni");
+        assert_eq!(format_duration_in(1e20, UiLanguage::Pl), "3.2e12 lat");
+        assert!(format_strength_in(&PasswordStrength::from_words(1296, 2), UiLanguage::Pl).starts_with("entropia (w bitach): 20.7\n"));
+    }
+}
@@ -0,0 +1,240 @@
+use std::str::FromStr;
+
+use dices;
+use messages::Message;
+use policy::POLICY_SYMBOLS;
+use strength::word_entropy;
+
+/// Single element of a password template.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// word randomly chosen from the word list
+    Word,
+    /// given number of random digits
+    Digits(usize),
+    /// given number of random symbols
+    Symbols(usize),
+    /// text copied to the password as is
+    Literal(String),
+}
+
+/// Template describing structure of a password, i.e. "W.W.W.D2.S" or "{word}-{word}{digits:3}{symbol}".
+///
+/// Two notations are supported:
+/// - compact: `W` - word, `D<n>` - n digits, `S<n>` - n symbols (n defaults to 1), any other character is copied as is
+///   (`\` could be used to copy W, D or S literally). Letters are case insensitive.
+/// - braces (used if template contains `{`): `{word}`, `{digits:<n>}`, `{symbols:<n>}` (singular forms and
+///   one-letter names `{w}`, `{d}`, `{s}` are accepted as well), text outside of braces is copied as is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub tokens: Vec<Token>,
+}
+
+impl FromStr for Template {
+    type Err = Message;
+
+    fn from_str(template: &str) -> Result<Template, Message> {
+        let tokens = match template.contains('{') {
+            true => parse_braces(template)?,
+            false => parse_compact(template),
+        };
+        Ok(Template { tokens })
+    }
+}
+
+impl Template {
+    /// Number of words password generated using this template consists of.
+    pub fn word_count(&self) -> usize {
+        self.tokens.iter().filter(|token| **token == Token::Word).count()
+    }
+
+    /// Checks if template contains words not separated by anything.
+    pub fn has_adjacent_words(&self) -> bool {
+        self.tokens.windows(2).any(|pair| pair[0] == Token::Word && pair[1] == Token::Word)
+    }
+
+    /// Entropy (in bits) of a password generated using this template and a list of `list_size` words.
+    pub fn entropy(&self, list_size: usize) -> f64 {
+        self.tokens.iter()
+            .map(|token| match *token {
+                Token::Word => word_entropy(list_size),
+                Token::Digits(count) => word_entropy(10) * count as f64,
+                Token::Symbols(count) => word_entropy(POLICY_SYMBOLS.len()) * count as f64,
+                Token::Literal(_) => 0.0,
+            })
+            .sum()
+    }
+
+    /// Builds a password using given (already chosen) words, digits and symbols are chosen randomly.
+    pub fn render(&self, words: &[String]) -> String {
+        let symbols = POLICY_SYMBOLS.chars().collect::<Vec<char>>();
+        let mut words = words.iter();
+        let mut password = String::new();
+        for token in &self.tokens {
+            match *token {
+                Token::Word => password.push_str(words.next().expect("not enough words to fill the template")),
+                Token::Digits(count) => (0..count).for_each(|_| {
+                    password.push_str(&dices::get_random_number(10).to_string())
+                }),
+                Token::Symbols(count) => (0..count).for_each(|_| {
+                    password.push(symbols[dices::get_random_number(symbols.len() as u32) as usize])
+                }),
+                Token::Literal(ref text) => password.push_str(text),
+            }
+        }
+        password
+    }
+}
+
+fn push_literal(tokens: &mut Vec<Token>, c: char) {
+    if let Some(&mut Token::Literal(ref mut text)) = tokens.last_mut() {
+        text.push(c);
+        return;
+    }
+    tokens.push(Token::Literal(c.to_string()));
+}
+
+fn parse_compact(template: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c.to_ascii_uppercase() {
+            'W' => tokens.push(Token::Word),
+            'D' | 'S' => {
+                let mut count = String::new();
+                while let Some(digit) = chars.peek().cloned().filter(|c| c.is_ascii_digit()) {
+                    count.push(digit);
+                    chars.next();
+                }
+                let count = count.parse::<usize>().unwrap_or(1);
+                tokens.push(match c.to_ascii_uppercase() {
+                    'D' => Token::Digits(count),
+                    _ => Token::Symbols(count),
+                });
+            }
+            '\\' => if let Some(escaped) = chars.next() {
+                push_literal(&mut tokens, escaped);
+            },
+            _ => push_literal(&mut tokens, c),
+        }
+    }
+    tokens
+}
+
+fn parse_braces(template: &str) -> Result<Vec<Token>, Message> {
+    let mut tokens = Vec::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    return Err(Message::new("error.template.unclosed", &[]));
+                }
+                tokens.push(parse_placeholder(&placeholder)?);
+            }
+            '}' => return Err(Message::new("error.template.unexpected", &[])),
+            _ => push_literal(&mut tokens, c),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Errors quote the placeholder along with its braces (given as an argument, so messages contain no braces of their own).
+fn parse_placeholder(placeholder: &str) -> Result<Token, Message> {
+    let quoted = format!("{{{}}}", placeholder);
+    let (name, count) = match placeholder.find(':') {
+        Some(index) => (&placeholder[..index], Some(&placeholder[index + 1..])),
+        None => (placeholder, None),
+    };
+    let count = match count {
+        Some(count) => count.parse::<usize>()
+            .map_err(|_| Message::new("error.template.count", &[&quoted]))?,
+        None => 1,
+    };
+    match name.to_lowercase().as_str() {
+        "w" | "word" if count == 1 => Ok(Token::Word),
+        "d" | "digit" | "digits" => Ok(Token::Digits(count)),
+        "s" | "symbol" | "symbols" => Ok(Token::Symbols(count)),
+        _ => Err(Message::new("error.template.placeholder", &[&quoted])),
+    }
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::*;
+
+    #[test]
+    fn compact_template_is_parsed() {
+        let template = "W.W.W.D2.S".parse::<Template>().unwrap();
+        assert_eq!(template.tokens, vec![
+            Token::Word,
+            Token::Literal(s!(".")),
+            Token::Word,
+            Token::Literal(s!(".")),
+            Token::Word,
+            Token::Literal(s!(".")),
+            Token::Digits(2),
+            Token::Literal(s!(".")),
+            Token::Symbols(1),
+        ]);
+    }
+
+    #[test]
+    fn letters_could_be_escaped_in_compact_template() {
+        let template = "w\\w-d3".parse::<Template>().unwrap();
+        assert_eq!(template.tokens, vec![Token::Word, Token::Literal(s!("w-")), Token::Digits(3)]);
+    }
+
+    #[test]
+    fn template_with_braces_is_parsed() {
+        let template = "{word}-{word}{digits:3}{symbol}".parse::<Template>().unwrap();
+        assert_eq!(template.tokens, vec![
+            Token::Word,
+            Token::Literal(s!("-")),
+            Token::Word,
+            Token::Digits(3),
+            Token::Symbols(1),
+        ]);
+    }
+
+    #[test]
+    fn unknown_placeholders_are_reported() {
+        assert!("{word}-{emoji}".parse::<Template>().is_err());
+        assert!("{digits:many}".parse::<Template>().is_err());
+        assert!("{word}}".parse::<Template>().is_err());
+        assert!("{word".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn adjacent_words_are_detected() {
+        assert!("{word}{word}-{word}".parse::<Template>().unwrap().has_adjacent_words());
+        assert!(!"WD2W".parse::<Template>().unwrap().has_adjacent_words());
+    }
+
+    #[test]
+    fn entropy_sums_entropy_of_all_tokens() {
+        let template = "W.W.W.D2.S".parse::<Template>().unwrap();
+        let expected = 3.0 * 1296f64.log2() + 2.0 * 10f64.log2() + (POLICY_SYMBOLS.len() as f64).log2();
+        assert!((template.entropy(1296) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn password_is_rendered_according_to_template() {
+        let template = "{word}-{word}{digits:3}{symbol}".parse::<Template>().unwrap();
+        let password = template.render(&[s!("correct"), s!("horse")]);
+        assert!(password.starts_with("correct-horse"));
+        let suffix = password.chars().skip("correct-horse".len()).collect::<Vec<char>>();
+        assert_eq!(suffix.len(), 4);
+        assert!(suffix[..3].iter().all(|c| c.is_ascii_digit()));
+        assert!(POLICY_SYMBOLS.contains(suffix[3]));
+    }
+}
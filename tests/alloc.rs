@@ -0,0 +1,81 @@
+//! Checks that `PasswordGenerator::generate_into` does not allocate once it has been warmed up.
+//! Allocations are counted per thread, so tests running in parallel do not disturb each other.
+
+extern crate dpg;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use dpg::capitalization::Capitalization;
+use dpg::generator::{PasswordGenerator, PasswordGeneratorBuilder};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // thread local storage could be gone when the thread is being destroyed
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|allocations| allocations.get())
+}
+
+/// Number of allocations made while generating 1000 passwords (after a warm-up).
+fn allocations_of_generating(builder: PasswordGeneratorBuilder) -> usize {
+    let mut generator = builder.build().unwrap();
+    let mut password = String::with_capacity(256);
+    for _i in 0..10 {
+        password.clear();
+        generator.generate_into(&mut password);
+    }
+
+    let before = allocations();
+    for _i in 0..1000 {
+        password.clear();
+        generator.generate_into(&mut password);
+        assert!(!password.is_empty());
+    }
+    allocations() - before
+}
+
+#[test]
+fn generating_into_buffer_does_not_allocate() {
+    assert_eq!(allocations_of_generating(PasswordGenerator::builder()), 0);
+    assert_eq!(allocations_of_generating(PasswordGenerator::builder().list("pl").length(8).capitalization(Capitalization::Upper)), 0);
+    assert_eq!(
+        allocations_of_generating(PasswordGenerator::builder()
+            .length_range(4, 7)
+            .separator_set(&['-', '.', '_'])
+            .unique_words(true)
+            .capitalization(Capitalization::RandomLetter)
+            .simulate_dices(true)),
+        0
+    );
+}
+
+#[test]
+fn allocations_are_counted() {
+    let before = allocations();
+    let mut generator = PasswordGenerator::builder().build().unwrap();
+    generator.generate();
+    assert!(allocations() > before);
+}
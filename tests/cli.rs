@@ -0,0 +1,392 @@
+#[macro_use]
+extern crate dpg;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+/// Runs the command-line utility, returns exit code along with everything written to stdout and stderr.
+fn run(args: &[&str]) -> (i32, String, String) {
+    run_with_input(args, "")
+}
+
+/// Locale of the process is not used, so messages are always written in English.
+fn run_with_input(args: &[&str], input: &str) -> (i32, String, String) {
+    let env = env::vars()
+        .filter(|(variable, _)| !["LC_ALL", "LC_MESSAGES", "LANG"].contains(&variable.as_str()))
+        .collect::<HashMap<String, String>>();
+    run_with_env(args, &env, input)
+}
+
+fn run_with_env(args: &[&str], env: &HashMap<String, String>, input: &str) -> (i32, String, String) {
+    let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+    let mut stdout: Vec<u8> = Vec::new();
+    let mut stderr: Vec<u8> = Vec::new();
+    let exit_code = dpg::cli::run_with_env(args, env, &mut input.as_bytes(), &mut stdout, &mut stderr);
+
+    (exit_code, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+}
+
+#[test]
+fn passwords_and_their_strength_are_printed() {
+    let (exit_code, stdout, stderr) = run(&["dpg", "-w:4", "-p:3", "-s:."]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stderr, "");
+
+    let lines = stdout.lines().collect::<Vec<&str>>();
+    assert_eq!(lines[0], "generated password(s):");
+    assert!(lines[1..4].iter().all(|password| password.split('.').count() == 4));
+    assert_eq!(lines[4], "");
+    assert_eq!(lines[5], "entropy: 41.4 bits");
+}
+
+#[test]
+fn help_is_printed_to_stdout() {
+    let (exit_code, stdout, stderr) = run(&["dpg", "-h"]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout, dpg::option_parser::help_message(None));
+    assert_eq!(stderr, "");
+
+    let (exit_code, stdout, _stderr) = run(&["dpg", "help", "check"]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout, dpg::option_parser::help_message(Some("check")));
+}
+
+#[test]
+fn missing_arguments_are_reported() {
+    let (exit_code, stdout, stderr) = run(&["dpg"]);
+    assert_eq!(exit_code, 1);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "error: insufficient parameters. Type 'dpg -h' for help.\n");
+}
+
+#[test]
+fn invalid_values_are_reported() {
+    let (exit_code, stdout, stderr) = run(&["dpg", "-w:0"]);
+    assert_eq!(exit_code, 2);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "error: password should consist of at least 1 and max 255 words\n");
+}
+
+#[test]
+fn unknown_options_are_reported() {
+    let (exit_code, stdout, stderr) = run(&["dpg", "-q:yes"]);
+    assert_eq!(exit_code, 3);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "error: unknown option: '-q'\n");
+}
+
+#[test]
+fn closest_option_is_suggested_for_unknown_one() {
+    let (exit_code, _stdout, stderr) = run(&["dpg", "--separtor=.", "-w", "4"]);
+    assert_eq!(exit_code, 3);
+    assert_eq!(stderr, "error: unknown option: '--separtor' (did you mean '--separator'?)\n");
+}
+
+#[test]
+fn ambiguous_words_warning_is_printed_to_stderr() {
+    let (exit_code, _stdout, stderr) = run(&["dpg", "-l:pl", "-w:4", "-s:"]);
+    assert_eq!(exit_code, 0);
+    assert!(stderr.starts_with("warning: words are not separated"));
+}
+
+#[test]
+fn broken_output_is_reported() {
+    struct Broken;
+    impl io::Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let args = vec![s!("dpg"), s!("-w:4")];
+    let mut stderr: Vec<u8> = Vec::new();
+    let exit_code = dpg::cli::run_with_env(args, &HashMap::new(), &mut io::empty(), &mut Broken, &mut stderr);
+    assert_eq!(exit_code, 5);
+    assert_eq!(String::from_utf8(stderr).unwrap(), "error: unable to write passwords: disk full\n");
+}
+
+#[test]
+fn gen_command_generates_passwords() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "gen", "-w:4"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("generated password(s):\n"));
+}
+
+#[test]
+fn completions_and_manual_page_are_printed() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "completions", "bash"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.contains("complete -F _dpg dpg"));
+    assert!(stdout.contains("--separator-set"));
+
+    let (exit_code, stdout, _stderr) = run(&["dpg", "man"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with(".TH DPG 1"));
+
+    let (exit_code, _stdout, stderr) = run(&["dpg", "completions", "powershell"]);
+    assert_eq!(exit_code, 2);
+    assert!(stderr.contains("unsupported shell: 'powershell'"));
+}
+
+#[test]
+fn messages_are_written_in_language_of_locale_or_ui_lang() {
+    let mut env = HashMap::new();
+    env.insert(s!("LANG"), s!("pl_PL.UTF-8"));
+    let (exit_code, stdout, _stderr) = run_with_env(&["dpg", "-w:4"], &env, "");
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("wygenerowane hasła:\n"));
+    assert!(stdout.contains("szacowany czas złamania hasła:"));
+
+    let (exit_code, _stdout, stderr) = run_with_env(&["dpg", "--wrods", "4"], &env, "");
+    assert_eq!(exit_code, 3);
+    assert_eq!(stderr, "błąd: nieznana opcja: '--wrods' (czy chodziło o '--words'?)\n");
+
+    let (exit_code, stdout, _stderr) = run_with_env(&["dpg", "lists", "--ui-lang", "en"], &env, "");
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("available word lists:\n"));
+
+    let (exit_code, stdout, _stderr) = run(&["dpg", "help", "gen", "-g:pl"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.contains("-w, --words <number>         długość hasła (w słowach)"));
+
+    let (exit_code, _stdout, stderr) = run(&["dpg", "-w:4", "--ui-lang", "de"]);
+    assert_eq!(exit_code, 2);
+    assert!(stderr.starts_with("error: unsupported language of messages: 'de'"));
+
+    let (exit_code, _stdout, stderr) = run_with_env(&["dpg", "-w:3", "--policy", "emoji"], &env, "");
+    assert_eq!(exit_code, 2);
+    assert_eq!(stderr, "błąd: nieznana reguła zasad haseł: 'emoji'\n");
+
+    let (exit_code, _stdout, stderr) = run(&["dpg", "-g", "pl", "lookup", "-l:pl", "1111"]);
+    assert_eq!(exit_code, 2);
+    assert_eq!(stderr, "błąd: '1111': do wybrania słowa z listy 'pl' potrzeba wyników 5 rzutów kośćmi\n");
+}
+
+#[test]
+fn word_lists_are_listed() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "lists"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.contains("en    1296 words, 4 dice per word"));
+    assert!(stdout.contains("pl    3888 words, 5 dice per word"));
+}
+
+#[test]
+fn dice_rolls_and_words_are_looked_up() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "lookup", "1111", "abandoned"]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout, "1111 ardvark\n1112 abandoned\n");
+
+    let (exit_code, _stdout, stderr) = run(&["dpg", "lookup", "-l:pl", "1111"]);
+    assert_eq!(exit_code, 2);
+    assert_eq!(stderr, "error: '1111': 5 dice rolls are needed to pick a word from the 'pl' list\n");
+}
+
+#[test]
+fn strength_of_given_password_is_checked() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "check", "Ardvark-Abandoned-Ardvark-Abandoned"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("word list: en\nwords: 4\n\nentropy: 41.4 bits\n"));
+
+    let (exit_code, stdout, _stderr) = run_with_input(&["dpg", "check"], "absurd.zysk\n");
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("word list: pl\nwords: 2\n"));
+
+    let (exit_code, _stdout, _stderr) = run(&["dpg", "check", "qwerty-asdf"]);
+    assert_eq!(exit_code, 2);
+}
+
+#[test]
+fn password_is_composed_of_entered_dice_rolls() {
+    let (exit_code, stdout, stderr) = run_with_input(&["dpg", "roll", "-w:2", "-s:_"], "1111\n1112\n");
+    assert_eq!(exit_code, 0);
+    assert!(stderr.starts_with("enter 2 dice rolls"));
+    assert!(stdout.starts_with("generated password(s):\nardvark_abandoned\n\nentropy: 20.7 bits\n"));
+
+    let (exit_code, _stdout, _stderr) = run_with_input(&["dpg", "roll", "-w:3"], "1111 1112");
+    assert_eq!(exit_code, 2);
+}
+
+#[test]
+fn config_file_pointed_to_by_environment_is_used() {
+    let path = env::temp_dir().join(format!("dpg-config-{}.toml", std::process::id()));
+    fs::write(&path, "words = 3\nseparator = \"+\"\n\n[profile.long]\nwords = 8\n").unwrap();
+    let mut env = HashMap::new();
+    env.insert(s!("DPG_CONFIG"), path.to_string_lossy().to_string());
+
+    let run_with_config = |args: &[&str]| {
+        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+        let mut stdout: Vec<u8> = Vec::new();
+        let mut stderr: Vec<u8> = Vec::new();
+        let exit_code = dpg::cli::run_with_env(args, &env, &mut io::empty(), &mut stdout, &mut stderr);
+        (exit_code, String::from_utf8(stdout).unwrap(), String::from_utf8(stderr).unwrap())
+    };
+
+    let (exit_code, stdout, _stderr) = run_with_config(&["dpg", "gen"]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.lines().nth(1).unwrap().split('+').count(), 3);
+
+    let (exit_code, stdout, _stderr) = run_with_config(&["dpg", "--profile", "long", "-s:_"]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout.lines().nth(1).unwrap().split('_').count(), 8);
+
+    fs::write(&path, "words = 3\ncase = \"shouting\"\n").unwrap();
+    let (exit_code, _stdout, stderr) = run_with_config(&["dpg", "gen"]);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(exit_code, 6);
+    assert!(stderr.starts_with(&format!("error: {}:2: invalid value of 'case'", path.to_string_lossy())));
+}
+
+#[test]
+fn environment_variables_are_used_as_defaults() {
+    let mut env = HashMap::new();
+    env.insert(s!("DPG_WORDS"), s!("5"));
+    env.insert(s!("DPG_SEPARATOR"), s!("."));
+    env.insert(s!("DPG_COUNT"), s!("2"));
+    let mut stdout: Vec<u8> = Vec::new();
+    let exit_code = dpg::cli::run_with_env(vec![s!("dpg"), s!("gen")], &env, &mut io::empty(), &mut stdout, &mut Vec::new());
+    assert_eq!(exit_code, 0);
+    let stdout = String::from_utf8(stdout).unwrap();
+    assert!(stdout.lines().skip(1).take(2).all(|password| password.split('.').count() == 5));
+
+    let mut stdout: Vec<u8> = Vec::new();
+    let args = vec![s!("dpg"), s!("--show-config"), s!("-p:3")];
+    let exit_code = dpg::cli::run_with_env(args, &env, &mut io::empty(), &mut stdout, &mut Vec::new());
+    assert_eq!(exit_code, 0);
+    let stdout = String::from_utf8(stdout).unwrap();
+    assert!(stdout.starts_with("effective settings:\n"));
+    assert!(stdout.contains("  words             '5'             environment variable DPG_WORDS\n"));
+    assert!(stdout.contains("  count             '3'             command line\n"));
+    assert!(stdout.contains("  language          'en'            default\n"));
+}
+
+#[test]
+fn plain_output_contains_passwords_only() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-w:4", "-p:3", "--format", "plain"]);
+    assert_eq!(exit_code, 0);
+    let lines = stdout.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|password| password.split('-').count() == 4));
+}
+
+#[test]
+fn machine_readable_records_describe_passwords() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-w:4", "-p:2", "-s:.", "-o:ndjson"]);
+    assert_eq!(exit_code, 0);
+    for record in stdout.lines() {
+        assert!(record.starts_with("{\"password\":\""));
+        assert!(record.ends_with(",\"list\":\"en\",\"word_count\":4,\"separator\":\".\",\"separators\":[],\"entropy\":41.36}"));
+    }
+
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-w:4", "-p:2", "-o:json"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("[\n  {"));
+    assert!(stdout.ends_with("}\n]\n"));
+
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-w:4", "-p:2", "-l:pl", "-o:csv"]);
+    assert_eq!(exit_code, 0);
+    let lines = stdout.lines().collect::<Vec<&str>>();
+    assert_eq!(lines[0], "password,words,list,word_count,separator,separators,entropy");
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].ends_with(",pl,4,-,,47.70"));
+}
+
+#[test]
+fn records_describe_separators_chosen_from_set_and_templates() {
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-w:3", "-j:._", "-o:ndjson"]);
+    assert_eq!(exit_code, 0);
+    let separators = stdout.split(",\"separator\":null,\"separators\":[").nth(1).unwrap().split(']').next().unwrap();
+    let separators = separators.split(',').collect::<Vec<&str>>();
+    assert_eq!(separators.len(), 2);
+    assert!(separators.iter().all(|&separator| separator == "\".\"" || separator == "\"_\""));
+
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-w:3", "-j:._", "-o:csv"]);
+    assert_eq!(exit_code, 0);
+    let record = stdout.lines().nth(1).unwrap();
+    let separators = record.split(',').nth(5).unwrap();
+    assert_eq!(separators.len(), 2);
+    assert!(separators.chars().all(|separator| separator == '.' || separator == '_'));
+
+    // digits and symbols of the template are not words
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-t:W.W.D2", "-o:ndjson"]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.contains(",\"word_count\":2,\"separator\":null,\"separators\":[],"));
+}
+
+#[test]
+fn unlimited_stream_ends_when_output_is_closed() {
+    /// Takes given number of lines (discarding the rest of the output written along with them, as passwords are written
+    /// in batches), then behaves as a pipe closed by the reader (i.e. `dpg -p:0 | head -n 500`).
+    struct Head {
+        lines: usize,
+        output: Vec<u8>,
+    }
+    impl io::Write for Head {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let lines = self.output.iter().filter(|&&byte| byte == b'\n').count();
+            if lines >= self.lines {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe"));
+            }
+            let taken = buf.iter()
+                .enumerate()
+                .filter(|&(_, &byte)| byte == b'\n')
+                .nth(self.lines - lines - 1)
+                .map_or(buf.len(), |(end, _)| end + 1);
+            self.output.extend_from_slice(&buf[..taken]);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let args = vec![s!("dpg"), s!("-w:3"), s!("-p:0"), s!("-o:plain")];
+    let mut head = Head { lines: 500, output: Vec::new() };
+    let mut stderr: Vec<u8> = Vec::new();
+    let exit_code = dpg::cli::run(args, &mut io::empty(), &mut head, &mut stderr);
+    assert_eq!(exit_code, 0);
+    assert_eq!(String::from_utf8(stderr).unwrap(), "");
+    let output = String::from_utf8(head.output).unwrap();
+    assert_eq!(output.lines().count(), 500);
+    assert!(output.lines().all(|password| !password.is_empty()));
+}
+
+#[test]
+fn passwords_are_generated_in_parallel() {
+    let (exit_code, stdout, stderr) = run(&["dpg", "-w:3", "-p:1000", "-a:4", "-o:plain"]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stderr, "");
+    assert_eq!(stdout.lines().count(), 1000);
+
+    let (exit_code, stdout, _stderr) = run(&["dpg", "-w:1", "-p:100", "-a:3", "-y", "-b", "-o:plain"]);
+    assert_eq!(exit_code, 0);
+    let mut passwords = stdout.lines().collect::<Vec<&str>>();
+    passwords.sort();
+    passwords.dedup();
+    assert_eq!(passwords.len(), 100);
+}
+
+/// Runs the executable itself, so everything the process writes to its stdout is checked (not only the injected writer).
+fn run_executable(args: &[&str]) -> String {
+    let output = process::Command::new(env!("CARGO_BIN_EXE_dpg"))
+        .args(args)
+        .env_remove("DPG_CONFIG")
+        .env("HOME", env::temp_dir())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn nothing_but_the_output_is_written_to_stdout() {
+    assert!(run_executable(&["completions", "bash"]).starts_with("# bash completion for dpg"));
+    assert!(run_executable(&["man"]).starts_with(".TH DPG 1"));
+    assert!(run_executable(&["-w", "3", "--format", "json"]).starts_with("[\n  {\"password\":"));
+    assert_eq!(run_executable(&["-w:3", "-p:5", "-o:csv"]).lines().count(), 6);
+}
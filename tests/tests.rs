@@ -2,67 +2,84 @@
 extern crate dpg;
 
 #[test]
-#[should_panic]
 fn calling_dpg_without_any_argument_should_fail() {
     let args = vec![s!("./dpg")];
-    let options = dpg::option_parser::parse_command_line(args);
-    let _passwords = dpg::generate_diceware_passwords(options);
+    let error = dpg::option_parser::parse_command_line(args).unwrap_err();
+    assert_eq!(error.exit_code(), 1);
 }
 
 #[test]
 fn generate_passwords_without_specifying_language() {
     let args = vec![s!("./dpg"), s!("-w:4"), s!("-p:1")];
-    let options = dpg::option_parser::parse_command_line(args);
-    let passwords = dpg::generate_diceware_passwords(options);
-    assert!(passwords.len() > 0);
-    assert_eq!(passwords.lines().count(), 1);
+    let options = dpg::option_parser::parse_command_line(args).unwrap();
+    let passwords = dpg::generate_diceware_passwords(options).unwrap();
+    assert_eq!(passwords.len(), 1);
+    assert!(!passwords[0].as_str().is_empty());
 }
 
 #[test]
 fn generate_password_using_polish_wordlist() {
     let args = vec![s!("./dpg"), s!("-l:pl"), s!("-w:4"), s!("-p:1")];
-    let options = dpg::option_parser::parse_command_line(args);
-    let passwords = dpg::generate_diceware_passwords(options);
-    assert!(passwords.len() > 0);
-    assert_eq!(passwords.lines().count(), 1);
+    let options = dpg::option_parser::parse_command_line(args).unwrap();
+    let passwords = dpg::generate_diceware_passwords(options).unwrap();
+    assert_eq!(passwords.len(), 1);
+    assert!(!passwords[0].as_str().is_empty());
 }
 
 #[test]
-#[should_panic(expected = "2")]
 fn generate_password_specifying_unsupported_language_should_fail() {
     let args = vec![s!("./dpg"), s!("-l:xy"), s!("-w:4"), s!("-p:1")];
-    let options = dpg::option_parser::parse_command_line(args);
-    dpg::generate_diceware_passwords(options);
+    let error = dpg::option_parser::parse_command_line(args).unwrap_err();
+    assert_eq!(error.exit_code(), 2);
 }
 
 #[test]
-#[should_panic(expected = "1")]
 fn should_exit_early_if_no_parameters_are_given() {
     let args = vec![s!("dpg")];
-    let options = dpg::option_parser::parse_command_line(args);
-    dpg::generate_diceware_passwords(options);
+    let error = dpg::option_parser::parse_command_line(args).unwrap_err();
+    assert_eq!(error.exit_code(), 1);
 }
 
 #[test]
-#[should_panic(expected = "2")]
 fn should_fail_if_asked_to_generate_password_consisting_of_less_than_1_word() {
     let args = vec![s!("dpg"), s!("-w:0")];
-    let options = dpg::option_parser::parse_command_line(args);
-    dpg::generate_diceware_passwords(options);
+    let error = dpg::option_parser::parse_command_line(args).unwrap_err();
+    assert_eq!(error.exit_code(), 2);
 }
 
 #[test]
-#[should_panic(expected = "2")]
 fn should_fail_if_asked_to_generate_password_longer_than_255_words() {
     let args = vec![s!("dpg"), s!("-w:256")];
-    let options = dpg::option_parser::parse_command_line(args);
-    dpg::generate_diceware_passwords(options);
+    let error = dpg::option_parser::parse_command_line(args).unwrap_err();
+    assert_eq!(error.exit_code(), 2);
 }
 
 #[test]
-#[should_panic(expected = "3")]
 fn should_fail_if_unknown_option_is_used() {
     let args = vec![s!("dpg"), s!("-q:yes")];
-    let options = dpg::option_parser::parse_command_line(args);
-    dpg::generate_diceware_passwords(options);
-}
\ No newline at end of file
+    let error = dpg::option_parser::parse_command_line(args).unwrap_err();
+    assert_eq!(error.exit_code(), 3);
+}
+
+#[test]
+fn passwords_that_could_not_be_generated_are_reported_instead_of_panicking() {
+    let args = vec![s!("dpg"), s!("-w:1"), s!("-p:2000")];
+    let mut options = dpg::option_parser::parse_command_line(args).unwrap();
+    options.unique_passwords = true;
+    let error = dpg::generate_diceware_passwords(options.clone()).unwrap_err();
+    assert_eq!(error.key(), "error.distinct_passwords");
+
+    options.unique_passwords = false;
+    options.policy = Some("min=200".parse().unwrap());
+    let error = dpg::generate_diceware_passwords_with_strength(options).err().unwrap();
+    assert_eq!(error.key(), "error.policy_not_met");
+}
+
+#[test]
+fn strength_of_generated_passwords_is_reported() {
+    let args = vec![s!("dpg"), s!("-w:6")];
+    let options = dpg::option_parser::parse_command_line(args).unwrap();
+    let generated = dpg::generate_diceware_passwords_with_strength(options).unwrap();
+    let expected_bits = 6.0 * 1296f64.log2();
+    assert!((generated.strength.bits() - expected_bits).abs() < 1e-9);
+}